@@ -1,30 +1,238 @@
 pub use arrow;
+pub use half;
 
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+#[cfg(feature = "json")]
+use std::io::BufRead;
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use arrow::array::Array;
+use arrow::array::ArrayBuilder;
+use arrow::array::ArrayRef;
+use arrow::array::ArrowNativeTypeOp;
+use arrow::array::AsArray;
+use arrow::array::BooleanArray;
+use arrow::array::BooleanBufferBuilder;
+use arrow::array::Decimal128Array;
+use arrow::array::Decimal128Builder;
+use arrow::array::DictionaryArray;
+use arrow::array::FixedSizeListArray;
+use arrow::array::FixedSizeListBuilder;
+use arrow::array::Float16Array;
+use arrow::array::Float32Array;
+use arrow::array::Float32Builder;
+use arrow::array::Float64Array;
+use arrow::array::Float64Builder;
+use arrow::array::ListArray;
+use arrow::array::ListBuilder;
 use arrow::array::PrimitiveArray;
 use arrow::array::PrimitiveBuilder;
+use arrow::array::Scalar;
+use arrow::array::StructArray;
+use arrow::array::TimestampMillisecondArray;
+use arrow::array::TimestampMillisecondBuilder;
+use arrow::array::TimestampNanosecondArray;
+use arrow::array::UInt64Array;
+use arrow::array::UInt64Builder;
+
+use arrow::buffer::BooleanBuffer;
+use arrow::buffer::NullBuffer;
+use arrow::buffer::ScalarBuffer;
 
+use arrow::datatypes::ArrowDictionaryKeyType;
+use arrow::datatypes::ArrowNativeType;
 use arrow::datatypes::ArrowPrimitiveType;
+use arrow::datatypes::Field;
+use arrow::datatypes::Fields;
+use arrow::datatypes::Int32Type;
+use arrow::datatypes::Schema;
+
+use arrow::error::ArrowError;
+
+use arrow::record_batch::RecordBatch;
 
 pub trait FloatType: ArrowPrimitiveType {
     fn is_nan(val: Self::Native) -> bool;
+
+    /// Checks if the value is `+inf` or `-inf`.
+    fn is_infinite(val: Self::Native) -> bool;
+
+    /// The NaN of this width.
+    fn nan() -> Self::Native;
+
+    /// Converts from `f64` (may round or overflow to infinity).
+    fn from_f64(val: f64) -> Self::Native;
+
+    /// Checks if the value is a signaling NaN.
+    ///
+    /// A NaN is signaling when the most significant bit of its mantissa (the
+    /// "quiet" bit, as in IEEE 754-2008 and on x86/ARM) is clear.
+    fn is_signaling_nan(val: Self::Native) -> bool;
+
+    /// Converts to `f64` (exact for every width).
+    fn to_f64(val: Self::Native) -> f64;
+
+    /// Checks if the `f64` survives narrowing to this width and widening back.
+    fn fits_exactly(val: f64) -> bool;
+
+    /// Decodes the little-endian bytes (exactly the width of `Self::Native`).
+    fn from_le_slice(bytes: &[u8]) -> Self::Native;
+
+    /// Checks if the value is subnormal (nonzero and below the smallest normal).
+    fn is_subnormal(val: Self::Native) -> bool;
+
+    /// Serializes the value in the shortest form of this width (`f16` as `f32`).
+    #[cfg(feature = "serde")]
+    fn serialize_native<S>(val: Self::Native, ser: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer;
 }
 
 impl FloatType for arrow::datatypes::Float16Type {
     fn is_nan(val: Self::Native) -> bool {
         val.is_nan()
     }
+
+    fn is_infinite(val: Self::Native) -> bool {
+        val.is_infinite()
+    }
+
+    fn nan() -> Self::Native {
+        half::f16::NAN
+    }
+
+    fn from_f64(val: f64) -> Self::Native {
+        half::f16::from_f64(val)
+    }
+
+    fn is_signaling_nan(val: Self::Native) -> bool {
+        val.is_nan() && (val.to_bits() & 0x0200) == 0
+    }
+
+    fn to_f64(val: Self::Native) -> f64 {
+        val.to_f64()
+    }
+
+    fn fits_exactly(val: f64) -> bool {
+        val.is_nan() || half::f16::from_f64(val).to_f64().to_bits() == val.to_bits()
+    }
+
+    fn from_le_slice(bytes: &[u8]) -> Self::Native {
+        half::f16::from_le_bytes([bytes[0], bytes[1]])
+    }
+
+    fn is_subnormal(val: Self::Native) -> bool {
+        val.classify() == std::num::FpCategory::Subnormal
+    }
+
+    #[cfg(feature = "serde")]
+    fn serialize_native<S>(val: Self::Native, ser: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        ser.serialize_f32(val.to_f32())
+    }
 }
 
 impl FloatType for arrow::datatypes::Float32Type {
     fn is_nan(val: Self::Native) -> bool {
         val.is_nan()
     }
+
+    fn is_infinite(val: Self::Native) -> bool {
+        val.is_infinite()
+    }
+
+    fn nan() -> Self::Native {
+        f32::NAN
+    }
+
+    fn from_f64(val: f64) -> Self::Native {
+        val as f32
+    }
+
+    fn is_signaling_nan(val: Self::Native) -> bool {
+        val.is_nan() && (val.to_bits() & 0x0040_0000) == 0
+    }
+
+    fn to_f64(val: Self::Native) -> f64 {
+        f64::from(val)
+    }
+
+    fn fits_exactly(val: f64) -> bool {
+        val.is_nan() || f64::from(val as f32).to_bits() == val.to_bits()
+    }
+
+    fn from_le_slice(bytes: &[u8]) -> Self::Native {
+        f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    fn is_subnormal(val: Self::Native) -> bool {
+        val.is_subnormal()
+    }
+
+    #[cfg(feature = "serde")]
+    fn serialize_native<S>(val: Self::Native, ser: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        ser.serialize_f32(val)
+    }
 }
 
 impl FloatType for arrow::datatypes::Float64Type {
     fn is_nan(val: Self::Native) -> bool {
         val.is_nan()
     }
+
+    fn is_infinite(val: Self::Native) -> bool {
+        val.is_infinite()
+    }
+
+    fn nan() -> Self::Native {
+        f64::NAN
+    }
+
+    fn from_f64(val: f64) -> Self::Native {
+        val
+    }
+
+    fn is_signaling_nan(val: Self::Native) -> bool {
+        val.is_nan() && (val.to_bits() & 0x0008_0000_0000_0000) == 0
+    }
+
+    fn to_f64(val: Self::Native) -> f64 {
+        val
+    }
+
+    fn fits_exactly(_val: f64) -> bool {
+        true
+    }
+
+    fn from_le_slice(bytes: &[u8]) -> Self::Native {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(bytes);
+        f64::from_le_bytes(buf)
+    }
+
+    fn is_subnormal(val: Self::Native) -> bool {
+        val.is_subnormal()
+    }
+
+    #[cfg(feature = "serde")]
+    fn serialize_native<S>(val: Self::Native, ser: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        ser.serialize_f64(val)
+    }
 }
 
 pub fn is_nan<N>(num: N::Native) -> bool
@@ -43,157 +251,4718 @@ where
     ok.then_some(num)
 }
 
-pub fn num2builder<T>(num: T::Native, bldr: &mut PrimitiveBuilder<T>)
+pub fn is_infinite<N>(num: N::Native) -> bool
+where
+    N: FloatType,
+{
+    N::is_infinite(num)
+}
+
+pub fn inf2none<N>(num: N::Native) -> Option<N::Native>
+where
+    N: FloatType,
+{
+    let inf: bool = is_infinite::<N>(num);
+    let ok: bool = !inf;
+    ok.then_some(num)
+}
+
+/// Converts NaN and infinities to `None`.
+pub fn nonfinite2none<N>(num: N::Native) -> Option<N::Native>
+where
+    N: FloatType,
+{
+    let nonfinite: bool = is_nan::<N>(num) || is_infinite::<N>(num);
+    let ok: bool = !nonfinite;
+    ok.then_some(num)
+}
+
+/// The position of the first NaN, if any (stops at the first hit).
+pub fn first_nan_index_of<I, T>(mut num: I) -> Option<usize>
 where
     T: FloatType,
+    I: Iterator<Item = T::Native>,
 {
-    let o: Option<T::Native> = nan2none::<T>(num);
-    match o {
-        None => bldr.append_null(),
-        Some(i) => bldr.append_value(i),
+    num.position(is_nan::<T>)
+}
+
+/// The position of the first NaN, if any (stops at the first hit).
+pub fn first_nan_index<I>(num: I) -> Option<usize>
+where
+    I: Iterator<Item = f64>,
+{
+    first_nan_index_of::<_, arrow::datatypes::Float64Type>(num)
+}
+
+/// A mask which is `true` where the value is finite (never null).
+pub fn finite_mask<T>(data: &[T::Native]) -> BooleanArray
+where
+    T: FloatType,
+{
+    data.iter()
+        .map(|&n| Some(nonfinite2none::<T>(n).is_some()))
+        .collect()
+}
+
+/// How a NaN input is stored in the array.
+#[derive(Default)]
+pub enum NanPolicy<T>
+where
+    T: FloatType,
+{
+    /// Appends a null (the default).
+    #[default]
+    ToNull,
+
+    /// Appends the NaN as a valid value.
+    Keep,
+
+    /// Appends the given value instead of the NaN.
+    Replace(T::Native),
+}
+
+impl<T> Clone for NanPolicy<T>
+where
+    T: FloatType,
+{
+    fn clone(&self) -> Self {
+        *self
     }
 }
 
-pub fn opt2builder<T>(num: Option<T::Native>, bldr: &mut PrimitiveBuilder<T>)
+impl<T> Copy for NanPolicy<T> where T: FloatType {}
+
+impl<T> std::fmt::Debug for NanPolicy<T>
 where
     T: FloatType,
 {
-    let o: Option<T::Native> = num.and_then(nan2none::<T>);
-    match o {
-        None => bldr.append_null(),
-        Some(i) => bldr.append_value(i),
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ToNull => f.write_str("ToNull"),
+            Self::Keep => f.write_str("Keep"),
+            Self::Replace(v) => f.debug_tuple("Replace").field(v).finish(),
+        }
     }
 }
 
-pub fn num2array<I, T>(num: I, cap: usize) -> PrimitiveArray<T>
+impl<T> NanPolicy<T>
 where
     T: FloatType,
-    I: Iterator<Item = T::Native>,
 {
-    let mut bldr = PrimitiveBuilder::with_capacity(cap);
+    /// Applies the policy to the number (`None` means null).
+    pub fn apply(self, num: T::Native) -> Option<T::Native> {
+        let nan: bool = is_nan::<T>(num);
+        match (nan, self) {
+            (false, _) => Some(num),
+            (true, Self::ToNull) => None,
+            (true, Self::Keep) => Some(num),
+            (true, Self::Replace(r)) => Some(r),
+        }
+    }
+}
 
-    for n in num {
-        num2builder(n, &mut bldr);
+/// How an infinite input is stored in the array.
+#[derive(Default)]
+pub enum InfPolicy<T>
+where
+    T: FloatType,
+{
+    /// Appends the infinity as a valid value (the default).
+    #[default]
+    Keep,
+
+    /// Appends a null.
+    ToNull,
+
+    /// Appends the given value instead of the infinity.
+    Replace(T::Native),
+}
+
+impl<T> Clone for InfPolicy<T>
+where
+    T: FloatType,
+{
+    fn clone(&self) -> Self {
+        *self
     }
+}
 
-    bldr.finish()
+impl<T> Copy for InfPolicy<T> where T: FloatType {}
+
+impl<T> std::fmt::Debug for InfPolicy<T>
+where
+    T: FloatType,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Keep => f.write_str("Keep"),
+            Self::ToNull => f.write_str("ToNull"),
+            Self::Replace(v) => f.debug_tuple("Replace").field(v).finish(),
+        }
+    }
 }
 
-pub fn opt2array<I, T>(num: I, cap: usize) -> PrimitiveArray<T>
+impl<T> InfPolicy<T>
 where
     T: FloatType,
-    I: Iterator<Item = Option<T::Native>>,
 {
-    let mut bldr = PrimitiveBuilder::with_capacity(cap);
+    /// Applies the policy to the number (`None` means null).
+    pub fn apply(self, num: T::Native) -> Option<T::Native> {
+        let inf: bool = is_infinite::<T>(num);
+        match (inf, self) {
+            (false, _) => Some(num),
+            (true, Self::Keep) => Some(num),
+            (true, Self::ToNull) => None,
+            (true, Self::Replace(r)) => Some(r),
+        }
+    }
+}
 
-    for n in num {
-        opt2builder(n, &mut bldr);
+pub fn num2builder_with<T>(num: T::Native, policy: NanPolicy<T>, bldr: &mut PrimitiveBuilder<T>)
+where
+    T: FloatType,
+{
+    let o: Option<T::Native> = policy.apply(num);
+    match o {
+        None => bldr.append_null(),
+        Some(i) => bldr.append_value(i),
     }
+}
 
-    bldr.finish()
+pub fn opt2builder_with<T>(
+    num: Option<T::Native>,
+    policy: NanPolicy<T>,
+    bldr: &mut PrimitiveBuilder<T>,
+) where
+    T: FloatType,
+{
+    match num {
+        None => bldr.append_null(),
+        Some(n) => num2builder_with(n, policy, bldr),
+    }
 }
 
-pub const CAPACITY_DEFAULT: usize = 1024;
+pub fn num2builder<T>(num: T::Native, bldr: &mut PrimitiveBuilder<T>)
+where
+    T: FloatType,
+{
+    num2builder_with(num, NanPolicy::ToNull, bldr)
+}
 
-pub fn num2array_default<I, T>(num: I) -> PrimitiveArray<T>
+pub fn opt2builder<T>(num: Option<T::Native>, bldr: &mut PrimitiveBuilder<T>)
+where
+    T: FloatType,
+{
+    opt2builder_with(num, NanPolicy::ToNull, bldr)
+}
+
+/// Appends the numbers to the builder; NaN becomes null.
+pub fn extend_builder<I, T>(bldr: &mut PrimitiveBuilder<T>, num: I)
 where
     T: FloatType,
     I: Iterator<Item = T::Native>,
 {
-    num2array(num, CAPACITY_DEFAULT)
+    for n in num {
+        num2builder(n, bldr);
+    }
 }
 
-pub fn opt2array_default<I, T>(num: I) -> PrimitiveArray<T>
+/// Appends the optionals to the builder; `None` and NaN become nulls.
+pub fn extend_builder_opt<I, T>(bldr: &mut PrimitiveBuilder<T>, num: I)
 where
     T: FloatType,
     I: Iterator<Item = Option<T::Native>>,
 {
-    opt2array(num, CAPACITY_DEFAULT)
+    for n in num {
+        opt2builder(n, bldr);
+    }
 }
 
-macro_rules! num2arr {
-    ($fname: ident, $ptyp: ty) => {
-        /// Converts the numbers to an array.
-        pub fn $fname<I>(num: I) -> PrimitiveArray<$ptyp>
-        where
-            I: Iterator<Item = <$ptyp as ArrowPrimitiveType>::Native>,
-        {
-            num2array_default(num)
-        }
-    };
+/// Converts numbers to arrays using the configured capacity and policies.
+pub struct Converter<T>
+where
+    T: FloatType,
+{
+    cap: usize,
+    nan: NanPolicy<T>,
+    inf: InfPolicy<T>,
 }
 
-num2arr!(num2arr16f, arrow::array::types::Float16Type);
-num2arr!(num2arr32f, arrow::array::types::Float32Type);
-num2arr!(num2arr64f, arrow::array::types::Float64Type);
-
-macro_rules! opt2arr {
-    ($fname: ident, $ptyp: ty) => {
-        /// Converts the optionals to an array.
-        pub fn $fname<I>(num: I) -> PrimitiveArray<$ptyp>
-        where
-            I: Iterator<Item = Option<<$ptyp as ArrowPrimitiveType>::Native>>,
-        {
-            opt2array_default(num)
-        }
-    };
+impl<T> Clone for Converter<T>
+where
+    T: FloatType,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
 }
 
-opt2arr!(opt2arr16f, arrow::array::types::Float16Type);
-opt2arr!(opt2arr32f, arrow::array::types::Float32Type);
-opt2arr!(opt2arr64f, arrow::array::types::Float64Type);
+impl<T> Copy for Converter<T> where T: FloatType {}
 
-pub fn num2opt(n: &serde_json::Number) -> Option<f64> {
-    let of: Option<f64> = n.as_f64();
-    of.and_then(nan2none::<arrow::datatypes::Float64Type>)
+impl<T> std::fmt::Debug for Converter<T>
+where
+    T: FloatType,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Converter")
+            .field("cap", &self.cap)
+            .field("nan", &self.nan)
+            .field("inf", &self.inf)
+            .finish()
+    }
 }
 
-pub fn val2opt(v: &serde_json::Value) -> Option<f64> {
-    match v {
-        serde_json::Value::Number(n) => num2opt(n),
-        _ => None,
+impl<T> Default for Converter<T>
+where
+    T: FloatType,
+{
+    /// [`CAPACITY_DEFAULT`], NaN to null, infinities kept.
+    fn default() -> Self {
+        Self {
+            cap: CAPACITY_DEFAULT,
+            nan: NanPolicy::default(),
+            inf: InfPolicy::default(),
+        }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use arrow::array::Array;
-    use arrow::array::Float32Array;
-
-    #[test]
-    fn test_num2arr32f_basic() {
-        // 1.0, NaN, 3.5
-        let data = vec![1.0f32, f32::NAN, 3.5f32];
-        let arr: Float32Array = num2arr32f(data.into_iter());
+impl<T> Converter<T>
+where
+    T: FloatType,
+{
+    pub fn with_capacity(mut self, cap: usize) -> Self {
+        self.cap = cap;
+        self
+    }
 
-        assert_eq!(arr.len(), 3);
-        assert_eq!(arr.null_count(), 1);
+    pub fn with_nan_policy(mut self, policy: NanPolicy<T>) -> Self {
+        self.nan = policy;
+        self
+    }
 
-        // Valid indices
-        assert!(arr.is_valid(0));
-        assert!(!arr.is_valid(1)); // NaN should become null
-        assert!(arr.is_valid(2));
+    pub fn with_inf_policy(mut self, policy: InfPolicy<T>) -> Self {
+        self.inf = policy;
+        self
+    }
 
-        // Values
-        assert_eq!(arr.value(0), 1.0);
-        assert_eq!(arr.value(2), 3.5);
+    /// Applies the policies to the number (`None` means null).
+    pub fn apply(&self, num: T::Native) -> Option<T::Native> {
+        if is_nan::<T>(num) {
+            self.nan.apply(num)
+        } else {
+            self.inf.apply(num)
+        }
     }
 
-    #[test]
-    fn test_num2arr32f_empty_and_all_nan() {
+    pub fn build<I>(&self, num: I) -> PrimitiveArray<T>
+    where
+        I: Iterator<Item = T::Native>,
+    {
+        let mut bldr = PrimitiveBuilder::with_capacity(self.cap);
+
+        for n in num {
+            bldr.append_option(self.apply(n));
+        }
+
+        bldr.finish()
+    }
+
+    pub fn build_opt<I>(&self, num: I) -> PrimitiveArray<T>
+    where
+        I: Iterator<Item = Option<T::Native>>,
+    {
+        let mut bldr = PrimitiveBuilder::with_capacity(self.cap);
+
+        for n in num {
+            bldr.append_option(n.and_then(|v| self.apply(v)));
+        }
+
+        bldr.finish()
+    }
+}
+
+pub fn num2array<I, T>(num: I, cap: usize) -> PrimitiveArray<T>
+where
+    T: FloatType,
+    I: Iterator<Item = T::Native>,
+{
+    Converter::default().with_capacity(cap).build(num)
+}
+
+pub fn opt2array<I, T>(num: I, cap: usize) -> PrimitiveArray<T>
+where
+    T: FloatType,
+    I: Iterator<Item = Option<T::Native>>,
+{
+    Converter::default().with_capacity(cap).build_opt(num)
+}
+
+/// Converts the numbers to an array; NaN and infinities become nulls.
+pub fn num2array_finite<I, T>(num: I, cap: usize) -> PrimitiveArray<T>
+where
+    T: FloatType,
+    I: Iterator<Item = T::Native>,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(cap);
+
+    for n in num {
+        bldr.append_option(nonfinite2none::<T>(n));
+    }
+
+    bldr.finish()
+}
+
+/// A NaN or an infinity was found where only finite values are allowed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NonFiniteError {
+    /// The position of the value.
+    pub index: usize,
+
+    /// The offending value.
+    pub value: f64,
+}
+
+impl std::fmt::Display for NonFiniteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "non-finite value {} at {}", self.value, self.index)
+    }
+}
+
+impl std::error::Error for NonFiniteError {}
+
+/// Converts the numbers to an array without nulls; the first NaN or infinity is an error.
+pub fn try_finite2arr64f<I>(num: I) -> Result<Float64Array, NonFiniteError>
+where
+    I: Iterator<Item = f64>,
+{
+    let mut bldr = Float64Builder::with_capacity(CAPACITY_DEFAULT);
+
+    for (index, value) in num.enumerate() {
+        if !value.is_finite() {
+            return Err(NonFiniteError { index, value });
+        }
+        bldr.append_value(value);
+    }
+
+    Ok(bldr.finish())
+}
+
+const CANCEL_CHECK_INTERVAL: usize = 4096;
+
+/// Converts the numbers to an array until `stop` is set; NaN becomes null.
+///
+/// The flag is checked before every block of 4096 elements, so up to one block
+/// may be converted after it is set. The result holds the elements converted
+/// before stopping.
+pub fn num2array_cancelable<I, T>(mut num: I, cap: usize, stop: &AtomicBool) -> PrimitiveArray<T>
+where
+    T: FloatType,
+    I: Iterator<Item = T::Native>,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(cap);
+
+    while !stop.load(Ordering::Relaxed) {
+        let start: usize = bldr.len();
+        for n in num.by_ref().take(CANCEL_CHECK_INTERVAL) {
+            num2builder(n, &mut bldr);
+        }
+        let exhausted: bool = bldr.len() - start < CANCEL_CHECK_INTERVAL;
+        if exhausted {
+            break;
+        }
+    }
+
+    bldr.finish()
+}
+
+/// Converts the numbers to arrays of at most `chunk_size` elements.
+///
+/// The last array may be shorter; an empty input yields no arrays.
+///
+/// # Panics
+///
+/// Panics if `chunk_size` is 0.
+pub fn num2chunks<I, T>(mut num: I, chunk_size: usize) -> impl Iterator<Item = PrimitiveArray<T>>
+where
+    T: FloatType,
+    I: Iterator<Item = T::Native>,
+{
+    assert!(chunk_size > 0, "chunk_size must be positive");
+    std::iter::from_fn(move || {
+        let cap: usize = chunk_size.min(num.size_hint().0);
+        let mut bldr = PrimitiveBuilder::with_capacity(cap);
+        for n in num.by_ref().take(chunk_size) {
+            num2builder(n, &mut bldr);
+        }
+        let empty: bool = bldr.is_empty();
+        let ok: bool = !empty;
+        ok.then(|| bldr.finish())
+    })
+}
+
+/// The number of elements whose values and validity fit in `max_bytes`.
+///
+/// `n` elements take `n * size_of::<Native>()` bytes of values plus `ceil(n / 8)`
+/// bytes of validity.
+fn elements_within_bytes<T>(max_bytes: usize) -> usize
+where
+    T: FloatType,
+{
+    let width: u128 = std::mem::size_of::<T::Native>() as u128;
+    let max: u128 = max_bytes as u128;
+    let bytes = |n: u128| n * width + n.div_ceil(8);
+    // Off by at most one from the answer, so each loop takes at most a step.
+    let mut n: u128 = max * 8 / (8 * width + 1);
+    while bytes(n + 1) <= max {
+        n += 1;
+    }
+    while n > 0 && bytes(n) > max {
+        n -= 1;
+    }
+    usize::try_from(n).expect("n does not exceed max_bytes")
+}
+
+/// Converts the numbers to arrays whose values and validity take at most
+/// `max_bytes` each.
+///
+/// Each array holds at least one element, even if `max_bytes` is too small for
+/// it. The buffer overhead (alignment, padding) is not counted.
+pub fn num2chunks_by_bytes<I, T>(
+    num: I,
+    max_bytes: usize,
+) -> impl Iterator<Item = PrimitiveArray<T>>
+where
+    T: FloatType,
+    I: Iterator<Item = T::Native>,
+{
+    let chunk_size: usize = elements_within_bytes::<T>(max_bytes).max(1);
+    num2chunks(num, chunk_size)
+}
+
+/// Converts the numbers to an array and counts the NaNs which became nulls.
+pub fn num2array_counted<I, T>(num: I, cap: usize) -> (PrimitiveArray<T>, usize)
+where
+    T: FloatType,
+    I: Iterator<Item = T::Native>,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(cap);
+    let mut nan_count: usize = 0;
+
+    for n in num {
+        let o: Option<T::Native> = nan2none::<T>(n);
+        nan_count += usize::from(o.is_none());
+        bldr.append_option(o);
+    }
+
+    (bldr.finish(), nan_count)
+}
+
+/// Converts the numbers to an array without nulls, keeping NaN as a value.
+///
+/// The mask is `true` where the value is NaN.
+pub fn num2array_keep_nan<I, T>(num: I, cap: usize) -> (PrimitiveArray<T>, BooleanArray)
+where
+    T: FloatType,
+    I: Iterator<Item = T::Native>,
+{
+    let mut values: Vec<T::Native> = Vec::with_capacity(cap);
+    let mut nans: Vec<bool> = Vec::with_capacity(cap);
+
+    for n in num {
+        values.push(n);
+        nans.push(is_nan::<T>(n));
+    }
+
+    let arr: PrimitiveArray<T> = PrimitiveArray::new(ScalarBuffer::from(values), None);
+    (arr, BooleanArray::from(nans))
+}
+
+/// Converts the numbers to an array and records the positions of the NaNs which
+/// became nulls (in ascending order).
+pub fn num2array_drop_log<I>(num: I, cap: usize) -> (Float64Array, Vec<usize>)
+where
+    I: Iterator<Item = f64>,
+{
+    let mut bldr = Float64Builder::with_capacity(cap);
+    let mut dropped: Vec<usize> = Vec::new();
+
+    for (i, n) in num.enumerate() {
+        let o: Option<f64> = nan2none::<arrow::datatypes::Float64Type>(n);
+        if o.is_none() {
+            dropped.push(i);
+        }
+        bldr.append_option(o);
+    }
+
+    (bldr.finish(), dropped)
+}
+
+/// Converts the numbers to an array, flushing subnormals to `+0.0`; NaN becomes null.
+///
+/// Zeros and normal values (including the smallest normal) are kept as is.
+pub fn num2array_ftz<I, T>(num: I, cap: usize) -> PrimitiveArray<T>
+where
+    T: FloatType,
+    I: Iterator<Item = T::Native>,
+{
+    let flush = |n: T::Native| {
+        if T::is_subnormal(n) {
+            T::Native::ZERO
+        } else {
+            n
+        }
+    };
+    num2array(num.map(flush), cap)
+}
+
+/// Converts the numbers to an array and an array of their bit patterns.
+///
+/// Both arrays have the same validity: NaN becomes null in each, so NaN
+/// payloads never reach the bits and the null slots always hold `0`, which keeps
+/// the bits deterministic for hashing. Note that `0.0` and `-0.0` differ in bits.
+pub fn num2array_with_bits<I>(num: I, cap: usize) -> (Float64Array, UInt64Array)
+where
+    I: Iterator<Item = f64>,
+{
+    let mut bldr = Float64Builder::with_capacity(cap);
+    let mut bits = UInt64Builder::with_capacity(cap);
+
+    for n in num {
+        let o: Option<f64> = nan2none::<arrow::datatypes::Float64Type>(n);
+        bldr.append_option(o);
+        bits.append_option(o.map(f64::to_bits));
+    }
+
+    (bldr.finish(), bits.finish())
+}
+
+/// The share of NaN inputs exceeded the allowed ratio.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TooManyNaN {
+    /// The observed share of NaN inputs.
+    pub ratio: f64,
+
+    /// The number of NaN inputs.
+    pub nan_count: usize,
+
+    /// The number of inputs.
+    pub len: usize,
+}
+
+impl std::fmt::Display for TooManyNaN {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of {} values are NaN (ratio {})",
+            self.nan_count, self.len, self.ratio
+        )
+    }
+}
+
+impl std::error::Error for TooManyNaN {}
+
+/// The error of [`num2array_max_nan_ratio`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NanRatioError {
+    /// The `max_ratio` is negative or NaN.
+    InvalidParam(InvalidParamError),
+
+    /// The share of NaN inputs exceeded `max_ratio`.
+    TooManyNaN(TooManyNaN),
+}
+
+impl std::fmt::Display for NanRatioError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidParam(e) => e.fmt(f),
+            Self::TooManyNaN(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for NanRatioError {}
+
+/// Converts the numbers to an array; fails if the share of NaNs exceeds `max_ratio`.
+///
+/// The ratio is only known at the end, so the array is built and then dropped
+/// on error. An empty input has a ratio of 0. A negative (or NaN) `max_ratio`
+/// is an error, checked before consuming the input.
+pub fn num2array_max_nan_ratio<I>(
+    num: I,
+    cap: usize,
+    max_ratio: f64,
+) -> Result<Float64Array, NanRatioError>
+where
+    I: Iterator<Item = f64>,
+{
+    let valid: bool = max_ratio >= 0.0;
+    if !valid {
+        return Err(NanRatioError::InvalidParam(InvalidParamError {
+            name: "max_ratio",
+        }));
+    }
+    let (arr, nan_count) = num2array_counted(num, cap);
+    let len: usize = arr.len();
+    let ratio: f64 = if len == 0 {
+        0.0
+    } else {
+        nan_count as f64 / len as f64
+    };
+    if ratio > max_ratio {
+        return Err(NanRatioError::TooManyNaN(TooManyNaN {
+            ratio,
+            nan_count,
+            len,
+        }));
+    }
+    Ok(arr)
+}
+
+/// Converts the optionals to an array.
+///
+/// Returns the array, the number of `None` inputs and the number of NaN inputs.
+pub fn opt2array_counted<I, T>(num: I, cap: usize) -> (PrimitiveArray<T>, usize, usize)
+where
+    T: FloatType,
+    I: Iterator<Item = Option<T::Native>>,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(cap);
+    let mut none_count: usize = 0;
+    let mut nan_count: usize = 0;
+
+    for o in num {
+        match o {
+            None => {
+                none_count += 1;
+                bldr.append_null();
+            }
+            Some(n) => {
+                let v: Option<T::Native> = nan2none::<T>(n);
+                nan_count += usize::from(v.is_none());
+                bldr.append_option(v);
+            }
+        }
+    }
+
+    (bldr.finish(), none_count, nan_count)
+}
+
+/// The statistics of the values seen while building an array.
+pub struct FloatStats<T>
+where
+    T: FloatType,
+{
+    /// The smallest non-NaN value (`None` if there is none).
+    pub min: Option<T::Native>,
+
+    /// The largest non-NaN value (`None` if there is none).
+    pub max: Option<T::Native>,
+
+    pub valid_count: usize,
+    pub null_count: usize,
+}
+
+impl<T> Clone for FloatStats<T>
+where
+    T: FloatType,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for FloatStats<T> where T: FloatType {}
+
+impl<T> Default for FloatStats<T>
+where
+    T: FloatType,
+{
+    fn default() -> Self {
+        Self {
+            min: None,
+            max: None,
+            valid_count: 0,
+            null_count: 0,
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for FloatStats<T>
+where
+    T: FloatType,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FloatStats")
+            .field("min", &self.min)
+            .field("max", &self.max)
+            .field("valid_count", &self.valid_count)
+            .field("null_count", &self.null_count)
+            .finish()
+    }
+}
+
+impl<T> FloatStats<T>
+where
+    T: FloatType,
+{
+    /// Updates the statistics with the (already NaN-checked) value.
+    pub fn update(&mut self, o: Option<T::Native>) {
+        let Some(v) = o else {
+            self.null_count += 1;
+            return;
+        };
+        self.valid_count += 1;
+        self.min = Some(self.min.map_or(v, |m| if v.is_lt(m) { v } else { m }));
+        self.max = Some(self.max.map_or(v, |m| if v.is_gt(m) { v } else { m }));
+    }
+}
+
+/// Converts the numbers to an array and computes the statistics in one pass.
+pub fn num2array_stats<I, T>(num: I, cap: usize) -> (PrimitiveArray<T>, FloatStats<T>)
+where
+    T: FloatType,
+    I: Iterator<Item = T::Native>,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(cap);
+    let mut stats: FloatStats<T> = FloatStats::default();
+
+    for n in num {
+        let o: Option<T::Native> = nan2none::<T>(n);
+        stats.update(o);
+        bldr.append_option(o);
+    }
+
+    (bldr.finish(), stats)
+}
+
+/// Converts the numbers to an array and sums the non-NaN values.
+///
+/// The sum is compensated (Kahan) to limit the rounding error on long inputs.
+/// If an infinity (or an overflow) is involved, the plain sum is returned, as
+/// the compensation would turn it into NaN. An empty input sums to `0.0`.
+pub fn num2array_with_sum<I>(num: I, cap: usize) -> (Float64Array, f64)
+where
+    I: Iterator<Item = f64>,
+{
+    let mut bldr = Float64Builder::with_capacity(cap);
+    let mut plain: f64 = 0.0;
+    let mut sum: f64 = 0.0;
+    let mut compensation: f64 = 0.0;
+
+    for n in num {
+        let o: Option<f64> = nan2none::<arrow::datatypes::Float64Type>(n);
+        if let Some(v) = o {
+            plain += v;
+            let y: f64 = v - compensation;
+            let t: f64 = sum + y;
+            compensation = (t - sum) - y;
+            sum = t;
+        }
+        bldr.append_option(o);
+    }
+
+    let total: f64 = if plain.is_finite() { sum } else { plain };
+    (bldr.finish(), total)
+}
+
+/// Where the nulls of an array came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NullTags {
+    /// The number of `None` inputs.
+    pub explicit_none_count: usize,
+
+    /// The number of NaN inputs.
+    pub nan_count: usize,
+}
+
+/// Converts the optionals to an array, telling the `None` nulls from the NaN nulls.
+pub fn opt2array_tagged<I, T>(num: I, cap: usize) -> (PrimitiveArray<T>, NullTags)
+where
+    T: FloatType,
+    I: Iterator<Item = Option<T::Native>>,
+{
+    let (arr, explicit_none_count, nan_count) = opt2array_counted(num, cap);
+    let tags = NullTags {
+        explicit_none_count,
+        nan_count,
+    };
+    (arr, tags)
+}
+
+/// A signaling NaN was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignalingNanError {
+    /// The position of the signaling NaN.
+    pub index: usize,
+}
+
+impl std::fmt::Display for SignalingNanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "signaling NaN at {}", self.index)
+    }
+}
+
+impl std::error::Error for SignalingNanError {}
+
+/// Converts the numbers to an array; quiet NaN becomes null, signaling NaN is an error.
+///
+/// See [`FloatType::is_signaling_nan`] for the bit convention. Note that some
+/// platforms (e.g., x87) may quiet a signaling NaN when it is moved around.
+pub fn num2array_snan_checked<I, T>(
+    num: I,
+    cap: usize,
+) -> Result<PrimitiveArray<T>, SignalingNanError>
+where
+    T: FloatType,
+    I: Iterator<Item = T::Native>,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(cap);
+
+    for (index, n) in num.enumerate() {
+        if T::is_signaling_nan(n) {
+            return Err(SignalingNanError { index });
+        }
+        num2builder(n, &mut bldr);
+    }
+
+    Ok(bldr.finish())
+}
+
+/// Lazily converts NaN to `None`.
+pub trait NanToNullExt: Iterator + Sized {
+    fn nan_to_null<T>(self) -> impl Iterator<Item = Option<T::Native>>
+    where
+        T: FloatType<Native = Self::Item>,
+    {
+        self.map(nan2none::<T>)
+    }
+}
+
+impl<I> NanToNullExt for I where I: Iterator {}
+
+/// Converts the numbers to an array; values failing `pred` become nulls.
+///
+/// NaN is checked first, so `pred` never sees a NaN.
+pub fn filter2array<I, T, F>(num: I, cap: usize, pred: F) -> PrimitiveArray<T>
+where
+    T: FloatType,
+    I: Iterator<Item = T::Native>,
+    F: Fn(T::Native) -> bool,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(cap);
+
+    for n in num {
+        let o: Option<T::Native> = nan2none::<T>(n).filter(|v| pred(*v));
+        bldr.append_option(o);
+    }
+
+    bldr.finish()
+}
+
+/// How [`clamp2array`] handles values outside of the range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClampMode {
+    /// Out-of-range values become nulls.
+    #[default]
+    ToNull,
+
+    /// Out-of-range values snap to the nearest bound.
+    Saturate,
+}
+
+/// Converts the numbers to an array, clamping them into `[lo, hi]`.
+///
+/// NaN always becomes null. `lo > hi` (or a NaN bound) is a logic error, checked in debug builds.
+pub fn clamp2array<I, T>(
+    num: I,
+    cap: usize,
+    lo: T::Native,
+    hi: T::Native,
+    mode: ClampMode,
+) -> PrimitiveArray<T>
+where
+    T: FloatType,
+    I: Iterator<Item = T::Native>,
+{
+    debug_assert!(lo <= hi, "lo must not be greater than hi");
+    let mut bldr = PrimitiveBuilder::with_capacity(cap);
+
+    for n in num {
+        let o: Option<T::Native> = nan2none::<T>(n).and_then(|v| match mode {
+            ClampMode::ToNull => (lo <= v && v <= hi).then_some(v),
+            ClampMode::Saturate if v < lo => Some(lo),
+            ClampMode::Saturate if hi < v => Some(hi),
+            ClampMode::Saturate => Some(v),
+        });
+        bldr.append_option(o);
+    }
+
+    bldr.finish()
+}
+
+/// Rewrites `-0.0` to `+0.0`; every other value (including subnormals) is kept.
+pub fn canonical_zero<T>(num: T::Native) -> T::Native
+where
+    T: FloatType,
+{
+    let zero: bool = num == T::Native::ZERO;
+    if zero { T::Native::ZERO } else { num }
+}
+
+/// Converts the numbers to an array; `-0.0` is stored as `+0.0` and NaN becomes null.
+pub fn num2array_canonical<I, T>(num: I, cap: usize) -> PrimitiveArray<T>
+where
+    T: FloatType,
+    I: Iterator<Item = T::Native>,
+{
+    num2array(num.map(canonical_zero::<T>), cap)
+}
+
+/// Rounds the number to `decimals` decimal places (half away from zero).
+///
+/// The arithmetic is done in `f64` for every width. A value so large that
+/// scaling it overflows has no fractional digits at that precision and is
+/// returned unchanged, as are infinities and NaN.
+pub fn round_decimals<T>(num: T::Native, decimals: u32) -> T::Native
+where
+    T: FloatType,
+{
+    let exp: i32 = i32::try_from(decimals).unwrap_or(i32::MAX);
+    let scale: f64 = 10f64.powi(exp);
+    let scaled: f64 = T::to_f64(num) * scale;
+    if !scaled.is_finite() {
+        return num;
+    }
+    T::from_f64(scaled.round() / scale)
+}
+
+/// Converts the numbers to an array, rounding them to `decimals` decimal places.
+///
+/// NaN becomes null; finite values stay finite (see [`round_decimals`]).
+pub fn round2array<I, T>(num: I, cap: usize, decimals: u32) -> PrimitiveArray<T>
+where
+    T: FloatType,
+    I: Iterator<Item = T::Native>,
+{
+    num2array(num.map(|n| round_decimals::<T>(n, decimals)), cap)
+}
+
+/// Converts the numbers to an array, transforming each value with `f` first.
+///
+/// The NaN check is done on the transformed value, so a transform producing
+/// NaN still yields a null.
+pub fn map2array<I, T, F>(num: I, cap: usize, f: F) -> PrimitiveArray<T>
+where
+    T: FloatType,
+    I: Iterator<Item = T::Native>,
+    F: Fn(T::Native) -> T::Native,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(cap);
+
+    for n in num {
+        num2builder(f(n), &mut bldr);
+    }
+
+    bldr.finish()
+}
+
+/// Converts the numbers to an array; a slot is valid only if its `valid` flag is
+/// true and the value is not NaN.
+///
+/// If the iterators differ in length, the longer one is truncated.
+pub fn num2array_with_validity<I, V, T>(num: I, valid: V, cap: usize) -> PrimitiveArray<T>
+where
+    T: FloatType,
+    I: Iterator<Item = T::Native>,
+    V: Iterator<Item = bool>,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(cap);
+
+    for (n, ok) in num.zip(valid) {
+        let o: Option<T::Native> = ok.then_some(n).and_then(nan2none::<T>);
+        bldr.append_option(o);
+    }
+
+    bldr.finish()
+}
+
+/// A parameter is out of its valid range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidParamError {
+    /// The name of the parameter.
+    pub name: &'static str,
+}
+
+impl std::fmt::Display for InvalidParamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid {}", self.name)
+    }
+}
+
+impl std::error::Error for InvalidParamError {}
+
+/// Converts the numbers to an array, snapping them to the nearest multiple of `step`.
+///
+/// Computes `round(v / step) * step` where ties round half to even (e.g., with
+/// step `1.0`, `0.5` becomes `0.0` and `1.5` becomes `2.0`). The arithmetic is
+/// done in `f64` for every width; a value so large that `v / step` overflows is
+/// already on the grid and is kept as is. NaN becomes null. A `step` which is
+/// not positive (including NaN) is an error.
+pub fn quantize2array<I, T>(
+    num: I,
+    cap: usize,
+    step: T::Native,
+) -> Result<PrimitiveArray<T>, InvalidParamError>
+where
+    T: FloatType,
+    I: Iterator<Item = T::Native>,
+{
+    let positive: bool = step.partial_cmp(&T::Native::ZERO) == Some(std::cmp::Ordering::Greater);
+    if !positive {
+        return Err(InvalidParamError { name: "step" });
+    }
+    let step: f64 = T::to_f64(step);
+    let snap = |v: T::Native| {
+        let scaled: f64 = T::to_f64(v) / step;
+        if !scaled.is_finite() {
+            return v;
+        }
+        T::from_f64(scaled.round_ties_even() * step)
+    };
+    Ok(num2array(num.map(snap), cap))
+}
+
+/// Converts the numbers to an array; values with `|v| < epsilon` and NaN become nulls.
+///
+/// The comparison is strict, so values of exactly `epsilon` are kept and an
+/// `epsilon` of `0.0` only nulls NaN. A negative (or NaN) `epsilon` is an error.
+pub fn denoise2arr64f<I>(num: I, epsilon: f64) -> Result<Float64Array, InvalidParamError>
+where
+    I: Iterator<Item = f64>,
+{
+    let valid: bool = epsilon >= 0.0;
+    if !valid {
+        return Err(InvalidParamError { name: "epsilon" });
+    }
+    let mut bldr = Float64Builder::with_capacity(CAPACITY_DEFAULT);
+    for n in num {
+        let noise: bool = n.abs() < epsilon;
+        let o: Option<f64> = nan2none::<arrow::datatypes::Float64Type>(n).filter(|_| !noise);
+        bldr.append_option(o);
+    }
+    Ok(bldr.finish())
+}
+
+pub const CAPACITY_DEFAULT: usize = 1024;
+
+pub fn num2array_default<I, T>(num: I) -> PrimitiveArray<T>
+where
+    T: FloatType,
+    I: Iterator<Item = T::Native>,
+{
+    num2array(num, CAPACITY_DEFAULT)
+}
+
+pub fn opt2array_default<I, T>(num: I) -> PrimitiveArray<T>
+where
+    T: FloatType,
+    I: Iterator<Item = Option<T::Native>>,
+{
+    opt2array(num, CAPACITY_DEFAULT)
+}
+
+/// Same as [`num2array_default`], but callable as `num2array_of::<T>(num)`.
+pub fn num2array_of<T>(num: impl Iterator<Item = T::Native>) -> PrimitiveArray<T>
+where
+    T: FloatType,
+{
+    num2array_default(num)
+}
+
+/// Same as [`opt2array_default`], but callable as `opt2array_of::<T>(num)`.
+pub fn opt2array_of<T>(num: impl Iterator<Item = Option<T::Native>>) -> PrimitiveArray<T>
+where
+    T: FloatType,
+{
+    opt2array_default(num)
+}
+
+/// Checks if every slot is null; an empty array is all null.
+///
+/// Stops at the first valid slot.
+pub fn array_is_all_null<T>(arr: &PrimitiveArray<T>) -> bool
+where
+    T: FloatType,
+{
+    match arr.nulls() {
+        None => arr.is_empty(),
+        Some(n) => !n.inner().iter().any(|valid| valid),
+    }
+}
+
+/// Converts the numbers to an array sized from the iterator length.
+pub fn num2array_exact<I, T>(num: I) -> PrimitiveArray<T>
+where
+    T: FloatType,
+    I: ExactSizeIterator<Item = T::Native>,
+{
+    let cap: usize = num.len();
+    num2array(num, cap)
+}
+
+/// Converts the optionals to an array sized from the iterator length.
+pub fn opt2array_exact<I, T>(num: I) -> PrimitiveArray<T>
+where
+    T: FloatType,
+    I: ExactSizeIterator<Item = Option<T::Native>>,
+{
+    let cap: usize = num.len();
+    opt2array(num, cap)
+}
+
+/// Converts the slice to an array without going through a builder.
+///
+/// The values are copied in bulk; no null buffer is built if the slice has no NaN.
+pub fn slice2array<T>(data: &[T::Native]) -> PrimitiveArray<T>
+where
+    T: FloatType,
+{
+    slice2array_opt_nulls(data).0
+}
+
+/// Converts the slice to an array and tells whether any NaN was seen.
+///
+/// The values are copied in bulk and then scanned once for NaN: the validity
+/// bitmap is only started at the first NaN, so a clean slice yields an array
+/// without a null buffer.
+pub fn slice2array_opt_nulls<T>(data: &[T::Native]) -> (PrimitiveArray<T>, bool)
+where
+    T: FloatType,
+{
+    let values: ScalarBuffer<T::Native> = ScalarBuffer::from(data.to_vec());
+    let nulls: Option<NullBuffer> = nan2nulls::<T>(data);
+    let any_nan: bool = nulls.is_some();
+    (PrimitiveArray::new(values, nulls), any_nan)
+}
+
+/// The null buffer marking the NaNs, if there are any (in a single scan).
+fn nan2nulls<T>(data: &[T::Native]) -> Option<NullBuffer>
+where
+    T: FloatType,
+{
+    let first_nan: usize = data.iter().position(|n| is_nan::<T>(*n))?;
+    let mut valid = BooleanBufferBuilder::new(data.len());
+    valid.append_n(first_nan, true);
+    for n in &data[first_nan..] {
+        valid.append(!is_nan::<T>(*n));
+    }
+    Some(NullBuffer::new(valid.finish()))
+}
+
+/// Converts the vector to an array without copying and without a null buffer.
+///
+/// The values are not scanned: NaN is stored as a valid value. Use
+/// [`vec2array`] unless the data is known to be NaN-free.
+pub fn vec2array_unchecked<T>(data: Vec<T::Native>) -> PrimitiveArray<T>
+where
+    T: FloatType,
+{
+    PrimitiveArray::new(ScalarBuffer::from(data), None)
+}
+
+/// Converts the vector to an array without copying the values; NaN becomes null.
+///
+/// No null buffer is built if the vector has no NaN.
+pub fn vec2array<T>(data: Vec<T::Native>) -> PrimitiveArray<T>
+where
+    T: FloatType,
+{
+    let nulls: Option<NullBuffer> = nan2nulls::<T>(&data);
+    PrimitiveArray::new(ScalarBuffer::from(data), nulls)
+}
+
+/// Converts the slice of optionals to an array in one scan; `None` and NaN
+/// become nulls.
+///
+/// No null buffer is built if every value is valid.
+pub fn opt_slice2arr64f(data: &[Option<f64>]) -> Float64Array {
+    let mut values: Vec<f64> = Vec::with_capacity(data.len());
+    let mut valid = BooleanBufferBuilder::new(data.len());
+    let mut null_count: usize = 0;
+
+    for o in data {
+        let v: Option<f64> = o.and_then(nan2none::<arrow::datatypes::Float64Type>);
+        null_count += usize::from(v.is_none());
+        values.push(v.unwrap_or_default());
+        valid.append(v.is_some());
+    }
+
+    let nulls: Option<NullBuffer> = (null_count > 0).then(|| NullBuffer::new(valid.finish()));
+    PrimitiveArray::new(ScalarBuffer::from(values), nulls)
+}
+
+/// Creates an array of `len` nulls.
+pub fn all_null_array<T>(len: usize) -> PrimitiveArray<T>
+where
+    T: FloatType,
+{
+    PrimitiveArray::new_null(len)
+}
+
+/// Converts the NaN values of the array to nulls, preserving the existing nulls.
+///
+/// The values buffer is shared with the input; only a new null buffer is built
+/// (nothing is built if the array has no NaN).
+pub fn scrub_nan<T>(arr: &PrimitiveArray<T>) -> PrimitiveArray<T>
+where
+    T: FloatType,
+{
+    let values: &ScalarBuffer<T::Native> = arr.values();
+    let has_nan: bool = values.iter().any(|v| is_nan::<T>(*v));
+    if !has_nan {
+        return arr.clone();
+    }
+    let not_nan = BooleanBuffer::collect_bool(values.len(), |i| !is_nan::<T>(values[i]));
+    let nulls: Option<NullBuffer> = NullBuffer::union(arr.nulls(), Some(&NullBuffer::new(not_nan)));
+    PrimitiveArray::new(values.clone(), nulls)
+}
+
+/// Converts the array to optionals (`None` for nulls).
+pub fn array2vec<T>(arr: &PrimitiveArray<T>) -> Vec<Option<T::Native>>
+where
+    T: FloatType,
+{
+    arr.iter().collect()
+}
+
+/// Converts the array to numbers; nulls become NaN.
+pub fn array2vec_nan<T>(arr: &PrimitiveArray<T>) -> Vec<T::Native>
+where
+    T: FloatType,
+{
+    arr.iter().map(|o| o.unwrap_or_else(T::nan)).collect()
+}
+
+/// Splits the array into its valid values and the positions of its nulls.
+///
+/// Both are in ascending order; see [`sparse2arr64f`] for the reverse.
+pub fn split_nulls<T>(arr: &PrimitiveArray<T>) -> (Vec<T::Native>, Vec<usize>)
+where
+    T: FloatType,
+{
+    let mut values: Vec<T::Native> = Vec::with_capacity(arr.len() - arr.null_count());
+    let mut nulls: Vec<usize> = Vec::with_capacity(arr.null_count());
+
+    for (i, o) in arr.iter().enumerate() {
+        match o {
+            None => nulls.push(i),
+            Some(v) => values.push(v),
+        }
+    }
+
+    (values, nulls)
+}
+
+/// A reusable builder which applies the configured [`NanPolicy`].
+pub struct FloatArrayBuilder<T>
+where
+    T: FloatType,
+{
+    bldr: PrimitiveBuilder<T>,
+    policy: NanPolicy<T>,
+}
+
+impl<T> Default for FloatArrayBuilder<T>
+where
+    T: FloatType,
+{
+    fn default() -> Self {
+        Self::with_capacity(CAPACITY_DEFAULT)
+    }
+}
+
+impl<T> FloatArrayBuilder<T>
+where
+    T: FloatType,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(cap: usize) -> Self {
+        Self {
+            bldr: PrimitiveBuilder::with_capacity(cap),
+            policy: NanPolicy::default(),
+        }
+    }
+
+    pub fn with_nan_policy(mut self, policy: NanPolicy<T>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn nan_policy(&self) -> NanPolicy<T> {
+        self.policy
+    }
+
+    /// The number of values pushed so far.
+    pub fn len(&self) -> usize {
+        self.bldr.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bldr.is_empty()
+    }
+
+    pub fn push(&mut self, num: T::Native) {
+        num2builder_with(num, self.policy, &mut self.bldr)
+    }
+
+    pub fn push_opt(&mut self, num: Option<T::Native>) {
+        opt2builder_with(num, self.policy, &mut self.bldr)
+    }
+
+    pub fn extend<I>(&mut self, num: I)
+    where
+        I: Iterator<Item = T::Native>,
+    {
+        for n in num {
+            self.push(n);
+        }
+    }
+
+    pub fn extend_opt<I>(&mut self, num: I)
+    where
+        I: Iterator<Item = Option<T::Native>>,
+    {
+        for n in num {
+            self.push_opt(n);
+        }
+    }
+
+    /// Builds the array; the builder is emptied and can be reused.
+    pub fn finish(&mut self) -> PrimitiveArray<T> {
+        self.bldr.finish()
+    }
+
+    /// Builds an array of the values pushed so far, leaving the builder intact.
+    ///
+    /// Each snapshot copies all the buffered values (and validity bits), so it
+    /// costs O(len) time and memory per call.
+    pub fn snapshot(&self) -> PrimitiveArray<T> {
+        self.bldr.finish_cloned()
+    }
+
+    /// Discards the values pushed so far.
+    pub fn reset(&mut self) {
+        let _: PrimitiveArray<T> = self.bldr.finish();
+    }
+}
+
+/// Collects the numbers into an array (NaN becomes null).
+pub trait CollectFloatArrayExt: Iterator + Sized {
+    fn collect_float_array<T>(self) -> PrimitiveArray<T>
+    where
+        T: FloatType<Native = Self::Item>,
+    {
+        num2array_default(self)
+    }
+
+    fn collect_float_array_with_capacity<T>(self, cap: usize) -> PrimitiveArray<T>
+    where
+        T: FloatType<Native = Self::Item>,
+    {
+        num2array(self, cap)
+    }
+}
+
+impl<I> CollectFloatArrayExt for I where I: Iterator {}
+
+macro_rules! num2arr {
+    ($fname: ident, $ptyp: ty) => {
+        /// Converts the numbers to an array.
+        pub fn $fname<I>(num: I) -> PrimitiveArray<$ptyp>
+        where
+            I: Iterator<Item = <$ptyp as ArrowPrimitiveType>::Native>,
+        {
+            num2array_default(num)
+        }
+    };
+}
+
+num2arr!(num2arr16f, arrow::array::types::Float16Type);
+num2arr!(num2arr32f, arrow::array::types::Float32Type);
+num2arr!(num2arr64f, arrow::array::types::Float64Type);
+
+/// Converts the `half::f16` numbers to an array; NaN becomes null.
+pub fn num2arr16f_native<I>(num: I) -> Float16Array
+where
+    I: Iterator<Item = half::f16>,
+{
+    num2array_default::<_, arrow::datatypes::Float16Type>(num)
+}
+
+macro_rules! opt2arr {
+    ($fname: ident, $ptyp: ty) => {
+        /// Converts the optionals to an array.
+        pub fn $fname<I>(num: I) -> PrimitiveArray<$ptyp>
+        where
+            I: Iterator<Item = Option<<$ptyp as ArrowPrimitiveType>::Native>>,
+        {
+            opt2array_default(num)
+        }
+    };
+}
+
+opt2arr!(opt2arr16f, arrow::array::types::Float16Type);
+opt2arr!(opt2arr32f, arrow::array::types::Float32Type);
+opt2arr!(opt2arr64f, arrow::array::types::Float64Type);
+
+/// Downcasts the array if its data type is that of `T`.
+pub fn as_float_array<T>(arr: &dyn Array) -> Option<&PrimitiveArray<T>>
+where
+    T: FloatType,
+{
+    arr.as_primitive_opt::<T>()
+}
+
+/// Wraps the array into a single-column batch (the column is nullable).
+pub fn array2batch<T>(arr: PrimitiveArray<T>, field_name: &str) -> Result<RecordBatch, ArrowError>
+where
+    T: FloatType,
+{
+    let field = Field::new(field_name, arr.data_type().clone(), true);
+    let schema = Schema::new(vec![field]);
+    let col: ArrayRef = Arc::new(arr);
+    RecordBatch::try_new(Arc::new(schema), vec![col])
+}
+
+/// Creates a record batch from the named columns (all nullable).
+///
+/// Every column must have the length of the first one; the first mismatched
+/// column is reported by name. An empty slice yields an empty batch.
+pub fn columns2batch(cols: &[(&str, Float64Array)]) -> Result<RecordBatch, ArrowError> {
+    let expected: usize = cols.first().map(|(_, a)| a.len()).unwrap_or(0);
+    if let Some((name, arr)) = cols.iter().find(|(_, a)| a.len() != expected) {
+        return Err(ArrowError::InvalidArgumentError(format!(
+            "column {name} has length {} but expected {expected}",
+            arr.len()
+        )));
+    }
+    let fields: Vec<Field> = cols
+        .iter()
+        .map(|(name, arr)| Field::new(*name, arr.data_type().clone(), true))
+        .collect();
+    let schema = Arc::new(Schema::new(fields));
+    if cols.is_empty() {
+        return Ok(RecordBatch::new_empty(schema));
+    }
+    let columns: Vec<ArrayRef> = cols
+        .iter()
+        .map(|(_, arr)| Arc::new(arr.clone()) as ArrayRef)
+        .collect();
+    RecordBatch::try_new(schema, columns)
+}
+
+/// Takes the primary value where it is valid, the fallback value otherwise.
+///
+/// A slot is null only if both are null; the lengths must match.
+pub fn coalesce<T>(
+    primary: &PrimitiveArray<T>,
+    fallback: &PrimitiveArray<T>,
+) -> Result<PrimitiveArray<T>, ArrowError>
+where
+    T: FloatType,
+{
+    if primary.len() != fallback.len() {
+        return Err(ArrowError::InvalidArgumentError(format!(
+            "length mismatch: primary {} vs fallback {}",
+            primary.len(),
+            fallback.len()
+        )));
+    }
+    let merged = primary.iter().zip(fallback.iter()).map(|(p, f)| p.or(f));
+    Ok(merged.collect())
+}
+
+/// Creates a record batch from the named columns in key order; NaN becomes null.
+///
+/// The columns must have equal lengths. An empty map yields a batch with no
+/// columns and zero rows.
+pub fn map2batch(cols: &BTreeMap<String, Vec<f64>>) -> Result<RecordBatch, ArrowError> {
+    let arrays: Vec<(&str, Float64Array)> = cols
+        .iter()
+        .map(|(name, vals)| (name.as_str(), slice2array(vals)))
+        .collect();
+    columns2batch(&arrays)
+}
+
+/// Concatenates the arrays, preserving the nulls.
+///
+/// An empty slice yields an empty array.
+pub fn concat_arrays<T>(arrays: &[PrimitiveArray<T>]) -> Result<PrimitiveArray<T>, ArrowError>
+where
+    T: FloatType,
+{
+    if arrays.is_empty() {
+        return Ok(PrimitiveBuilder::new().finish());
+    }
+    let refs: Vec<&dyn Array> = arrays.iter().map(|a| a as &dyn Array).collect();
+    let concatenated: ArrayRef = arrow::compute::concat(&refs)?;
+    Ok(concatenated.as_primitive::<T>().clone())
+}
+
+/// Builds a time series batch of a non-nullable `timestamp` column
+/// (nanoseconds) and a nullable value column (NaN becomes null).
+///
+/// The slices must have the same length.
+pub fn timeseries2batch(
+    ts: &[i64],
+    vals: &[f64],
+    value_name: &str,
+) -> Result<RecordBatch, ArrowError> {
+    if ts.len() != vals.len() {
+        return Err(ArrowError::InvalidArgumentError(format!(
+            "{} timestamps but {} values",
+            ts.len(),
+            vals.len()
+        )));
+    }
+    let times: TimestampNanosecondArray = TimestampNanosecondArray::from(ts.to_vec());
+    let values: Float64Array = slice2array(vals);
+    let schema = Schema::new(vec![
+        Field::new("timestamp", times.data_type().clone(), false),
+        Field::new(value_name, values.data_type().clone(), true),
+    ]);
+    let cols: Vec<ArrayRef> = vec![Arc::new(times), Arc::new(values)];
+    RecordBatch::try_new(Arc::new(schema), cols)
+}
+
+/// Serializes the numbers as an Arrow IPC stream of a single nullable column.
+#[cfg(feature = "ipc")]
+pub fn num2ipc64f<I>(num: I, field_name: &str) -> Result<Vec<u8>, ArrowError>
+where
+    I: Iterator<Item = f64>,
+{
+    let arr: Float64Array = num2arr64f(num);
+    let batch: RecordBatch = array2batch(arr, field_name)?;
+
+    let buf: Vec<u8> = Vec::new();
+    let mut wtr = arrow::ipc::writer::StreamWriter::try_new(buf, &batch.schema())?;
+    wtr.write(&batch)?;
+    wtr.finish()?;
+    wtr.into_inner()
+}
+
+/// Interleaves the arrays: `a[0], b[0], a[1], b[1], ...`, preserving the nulls.
+///
+/// The remaining tail of the longer array follows the interleaved prefix.
+pub fn interleave2<T>(a: &PrimitiveArray<T>, b: &PrimitiveArray<T>) -> PrimitiveArray<T>
+where
+    T: FloatType,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(a.len() + b.len());
+    let mut ia = a.iter();
+    let mut ib = b.iter();
+
+    loop {
+        let oa: Option<Option<T::Native>> = ia.next();
+        let ob: Option<Option<T::Native>> = ib.next();
+        if oa.is_none() && ob.is_none() {
+            break;
+        }
+        oa.into_iter().chain(ob).for_each(|o| bldr.append_option(o));
+    }
+
+    bldr.finish()
+}
+
+/// Assembles the named columns into a struct array (all fields are nullable).
+///
+/// All the columns must have the same length.
+pub fn columns2struct<T>(cols: &[(&str, PrimitiveArray<T>)]) -> Result<StructArray, ArrowError>
+where
+    T: FloatType,
+{
+    let Some((_, first)) = cols.first() else {
+        return Ok(StructArray::new_empty_fields(0, None));
+    };
+    let len: usize = first.len();
+
+    let mut fields: Vec<Field> = Vec::with_capacity(cols.len());
+    let mut arrays: Vec<ArrayRef> = Vec::with_capacity(cols.len());
+    for (name, arr) in cols {
+        if arr.len() != len {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "column {name} has length {}, expected {len}",
+                arr.len()
+            )));
+        }
+        fields.push(Field::new(*name, arr.data_type().clone(), true));
+        arrays.push(Arc::new(arr.clone()));
+    }
+
+    StructArray::try_new(Fields::from(fields), arrays, None)
+}
+
+/// Integer types; every value is valid.
+pub trait IntegralType: ArrowPrimitiveType {}
+
+impl IntegralType for arrow::datatypes::Int8Type {}
+impl IntegralType for arrow::datatypes::Int16Type {}
+impl IntegralType for arrow::datatypes::Int32Type {}
+impl IntegralType for arrow::datatypes::Int64Type {}
+impl IntegralType for arrow::datatypes::UInt8Type {}
+impl IntegralType for arrow::datatypes::UInt16Type {}
+impl IntegralType for arrow::datatypes::UInt32Type {}
+impl IntegralType for arrow::datatypes::UInt64Type {}
+
+pub fn int2array<I, T>(num: I, cap: usize) -> PrimitiveArray<T>
+where
+    T: IntegralType,
+    I: Iterator<Item = T::Native>,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(cap);
+
+    for n in num {
+        bldr.append_value(n);
+    }
+
+    bldr.finish()
+}
+
+pub fn int2array_default<I, T>(num: I) -> PrimitiveArray<T>
+where
+    T: IntegralType,
+    I: Iterator<Item = T::Native>,
+{
+    int2array(num, CAPACITY_DEFAULT)
+}
+
+macro_rules! int2arr {
+    ($fname: ident, $ptyp: ty) => {
+        /// Converts the integers to an array.
+        pub fn $fname<I>(num: I) -> PrimitiveArray<$ptyp>
+        where
+            I: Iterator<Item = <$ptyp as ArrowPrimitiveType>::Native>,
+        {
+            int2array_default(num)
+        }
+    };
+}
+
+int2arr!(int2arr8i, arrow::array::types::Int8Type);
+int2arr!(int2arr16i, arrow::array::types::Int16Type);
+int2arr!(int2arr32i, arrow::array::types::Int32Type);
+int2arr!(int2arr64i, arrow::array::types::Int64Type);
+int2arr!(int2arr8u, arrow::array::types::UInt8Type);
+int2arr!(int2arr16u, arrow::array::types::UInt16Type);
+int2arr!(int2arr32u, arrow::array::types::UInt32Type);
+int2arr!(int2arr64u, arrow::array::types::UInt64Type);
+
+/// Converts the rows to a list array; NaN inside a row becomes null.
+///
+/// Empty rows become empty (non-null) lists.
+pub fn nested2list64f<I, J>(rows: I) -> ListArray
+where
+    I: Iterator<Item = J>,
+    J: Iterator<Item = f64>,
+{
+    let mut bldr = ListBuilder::new(Float64Builder::with_capacity(CAPACITY_DEFAULT));
+
+    for row in rows {
+        for n in row {
+            num2builder(n, bldr.values());
+        }
+        bldr.append(true);
+    }
+
+    bldr.finish()
+}
+
+/// Converts the fixed-width vectors to a fixed size list array of width `D`.
+///
+/// NaN components become nulls; zero rows yield an empty list array of width `D`.
+///
+/// # Panics
+///
+/// Panics if `D` does not fit in `i32`.
+pub fn vectors2fixedlist32f<const D: usize, I>(rows: I) -> FixedSizeListArray
+where
+    I: Iterator<Item = [f32; D]>,
+{
+    let width: i32 = i32::try_from(D).expect("vector width must fit in i32");
+    let mut bldr =
+        FixedSizeListBuilder::new(Float32Builder::with_capacity(CAPACITY_DEFAULT), width);
+
+    for row in rows {
+        for n in row {
+            num2builder(n, bldr.values());
+        }
+        bldr.append(true);
+    }
+
+    bldr.finish()
+}
+
+/// The dictionary has more distinct values than the key type can index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyOverflowError {
+    /// The number of distinct values at the point of the overflow.
+    pub distinct: usize,
+}
+
+impl std::fmt::Display for KeyOverflowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "too many distinct values for the key type: {}",
+            self.distinct
+        )
+    }
+}
+
+impl std::error::Error for KeyOverflowError {}
+
+/// Dictionary-encodes the values with keys of type `K`; NaN becomes a null key.
+///
+/// Values are deduplicated by their bit pattern (so `0.0` and `-0.0` are
+/// distinct entries) and the dictionary is in first-seen order.
+pub fn num2dict64f_keyed<K, I>(num: I) -> Result<DictionaryArray<K>, KeyOverflowError>
+where
+    K: ArrowDictionaryKeyType,
+    I: Iterator<Item = f64>,
+{
+    let mut index: HashMap<u64, K::Native> = HashMap::new();
+    let mut dict: Vec<f64> = Vec::new();
+    let mut keys = PrimitiveBuilder::<K>::with_capacity(CAPACITY_DEFAULT);
+
+    for n in num {
+        let Some(v) = nan2none::<arrow::datatypes::Float64Type>(n) else {
+            keys.append_null();
+            continue;
+        };
+        let key: K::Native = match index.get(&v.to_bits()) {
+            Some(k) => *k,
+            None => {
+                let distinct: usize = dict.len();
+                let k: K::Native =
+                    K::Native::from_usize(distinct).ok_or(KeyOverflowError { distinct })?;
+                index.insert(v.to_bits(), k);
+                dict.push(v);
+                k
+            }
+        };
+        keys.append_value(key);
+    }
+
+    let values: ArrayRef = Arc::new(Float64Array::from(dict));
+    Ok(DictionaryArray::new(keys.finish(), values))
+}
+
+/// Dictionary-encodes the values; NaN becomes a null key.
+///
+/// See [`num2dict64f_keyed`].
+///
+/// # Panics
+///
+/// Panics if there are more than `i32::MAX + 1` distinct values.
+pub fn num2dict64f<I>(num: I) -> DictionaryArray<Int32Type>
+where
+    I: Iterator<Item = f64>,
+{
+    num2dict64f_keyed(num).expect("too many distinct values for Int32 keys")
+}
+
+/// Dictionary-encodes the values, merging values within `tol` of an entry; NaN
+/// becomes a null key.
+///
+/// The entries are the first-seen values, kept sorted so that the nearest one
+/// is found by binary search (ties go to the smaller entry). A value which is
+/// not within `|v - entry| <= tol` of its nearest entry becomes a new entry, so
+/// the result depends on the input order. The dictionary is in first-seen order.
+///
+/// Each new entry is inserted into the sorted entries, so high-cardinality input
+/// costs O(distinct²) moves. A negative (or NaN) `tol` is an error.
+///
+/// # Panics
+///
+/// Panics if there are more than `i32::MAX + 1` distinct entries.
+pub fn num2dict64f_tol<I>(num: I, tol: f64) -> Result<DictionaryArray<Int32Type>, InvalidParamError>
+where
+    I: Iterator<Item = f64>,
+{
+    let valid: bool = tol >= 0.0;
+    if !valid {
+        return Err(InvalidParamError { name: "tol" });
+    }
+    let mut sorted: Vec<(f64, i32)> = Vec::new();
+    let mut dict: Vec<f64> = Vec::new();
+    let mut keys = PrimitiveBuilder::<Int32Type>::with_capacity(CAPACITY_DEFAULT);
+
+    for n in num {
+        let Some(v) = nan2none::<arrow::datatypes::Float64Type>(n) else {
+            keys.append_null();
+            continue;
+        };
+        let pos: usize = sorted.partition_point(|(e, _)| *e < v);
+        let close = |i: usize| -> Option<(f64, i32)> {
+            let (e, k) = *sorted.get(i)?;
+            let dist: f64 = if e == v { 0.0 } else { (v - e).abs() };
+            (dist <= tol).then_some((dist, k))
+        };
+        let below: Option<(f64, i32)> = pos.checked_sub(1).and_then(close);
+        let above: Option<(f64, i32)> = close(pos);
+        let nearest: Option<i32> = match (below, above) {
+            (Some((db, kb)), Some((da, ka))) => Some(if da < db { ka } else { kb }),
+            (b, a) => b.or(a).map(|(_, k)| k),
+        };
+        let key: i32 = match nearest {
+            Some(k) => k,
+            None => {
+                let k: i32 =
+                    i32::try_from(dict.len()).expect("too many distinct values for Int32 keys");
+                sorted.insert(pos, (v, k));
+                dict.push(v);
+                k
+            }
+        };
+        keys.append_value(key);
+    }
+
+    let values: ArrayRef = Arc::new(Float64Array::from(dict));
+    Ok(DictionaryArray::new(keys.finish(), values))
+}
+
+/// Transposes the rows into one array per column; NaN becomes null.
+pub fn rows2columns<const N: usize, I>(rows: I) -> [Float64Array; N]
+where
+    I: Iterator<Item = [f64; N]>,
+{
+    let mut bldrs: [Float64Builder; N] =
+        std::array::from_fn(|_| Float64Builder::with_capacity(CAPACITY_DEFAULT));
+
+    for row in rows {
+        for (bldr, n) in bldrs.iter_mut().zip(row) {
+            num2builder(n, bldr);
+        }
+    }
+
+    bldrs.map(|mut b| b.finish())
+}
+
+/// Converts the `(value, is_valid)` pairs to an array; invalid or NaN values
+/// become nulls.
+pub fn pairs2arr64f<I>(num: I) -> Float64Array
+where
+    I: Iterator<Item = (f64, bool)>,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(CAPACITY_DEFAULT);
+
+    for (n, ok) in num {
+        opt2builder(ok.then_some(n), &mut bldr);
+    }
+
+    bldr.finish()
+}
+
+const MILLIS_PER_DAY: f64 = 86_400_000.0;
+
+/// Converts the fractional days since the epoch to millisecond timestamps.
+///
+/// Each value is scaled by `86_400_000` and rounded half away from zero. NaN,
+/// infinities and values outside of the `i64` range become nulls (instead of
+/// saturating, as `as i64` would).
+pub fn days_f64_2timestamp_ms<I>(num: I) -> TimestampMillisecondArray
+where
+    I: Iterator<Item = f64>,
+{
+    // 2^63 is exactly representable; every f64 in [-2^63, 2^63) fits in i64.
+    let limit: f64 = 9_223_372_036_854_775_808.0;
+    let mut bldr = TimestampMillisecondBuilder::with_capacity(CAPACITY_DEFAULT);
+
+    for n in num {
+        let ms: f64 = (n * MILLIS_PER_DAY).round();
+        let fits: bool = (-limit..limit).contains(&ms);
+        bldr.append_option(fits.then_some(ms as i64));
+    }
+
+    bldr.finish()
+}
+
+/// Scales the value by `10^scale` and rounds it (half away from zero) to an integer.
+///
+/// Returns `None` for NaN and for values which do not fit in `digits` decimal digits.
+fn f64_to_scaled_i128(v: f64, scale: i8, digits: u8) -> Option<i128> {
+    let limit: f64 = 10f64.powi(i32::from(digits));
+    let r: f64 = (v * 10f64.powi(i32::from(scale))).round();
+    (r.abs() < limit).then_some(r as i128)
+}
+
+/// Converts the numbers to decimals of the given scale (and the maximum precision).
+///
+/// Each value is multiplied by `10^scale` and rounded half away from zero, so
+/// `0.1` with scale 2 is stored as exactly `10`. NaN and values which overflow
+/// the precision become nulls.
+///
+/// # Panics
+///
+/// Panics if `scale` is greater than 38.
+pub fn f64_iter2dec128<I>(num: I, scale: i8) -> Decimal128Array
+where
+    I: Iterator<Item = f64>,
+{
+    let digits: u8 = arrow::datatypes::DECIMAL128_MAX_PRECISION;
+    let mut bldr = Decimal128Builder::with_capacity(CAPACITY_DEFAULT);
+
+    for n in num {
+        bldr.append_option(f64_to_scaled_i128(n, scale, digits));
+    }
+
+    bldr.finish()
+        .with_precision_and_scale(digits, scale)
+        .expect("scale must not exceed 38")
+}
+
+/// Converts the numbers to an array and summarizes the runs of equal values.
+///
+/// Each `(value, length)` entry is a run of consecutive equal non-NaN values;
+/// a NaN ends the current run and does not appear in the summary.
+pub fn num2array_runs<I>(num: I) -> (Float64Array, Vec<(f64, usize)>)
+where
+    I: Iterator<Item = f64>,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(CAPACITY_DEFAULT);
+    let mut runs: Vec<(f64, usize)> = Vec::new();
+    let mut in_run: bool = false;
+
+    for n in num {
+        let o: Option<f64> = nan2none::<arrow::datatypes::Float64Type>(n);
+        bldr.append_option(o);
+        match (o, runs.last_mut()) {
+            (None, _) => in_run = false,
+            (Some(v), Some((prev, len))) if in_run && *prev == v => *len += 1,
+            (Some(v), _) => {
+                runs.push((v, 1));
+                in_run = true;
+            }
+        }
+    }
+
+    (bldr.finish(), runs)
+}
+
+/// Converts the numbers to a sorted array, ordered by [`f64::total_cmp`].
+///
+/// With `keep_nan`, NaN values are kept and sorted by `total_cmp` (positive
+/// NaN after `+inf`, negative NaN before `-inf`); otherwise they become nulls,
+/// which are placed last. The sort is stable, so equal values keep their input
+/// order; `-0.0` sorts before `+0.0`.
+pub fn total_cmp2array<I>(num: I, ascending: bool, keep_nan: bool) -> Float64Array
+where
+    I: Iterator<Item = f64>,
+{
+    let mut vals: Vec<f64> = Vec::with_capacity(num.size_hint().0);
+    let mut null_count: usize = 0;
+
+    for n in num {
+        let keep: bool = keep_nan || !n.is_nan();
+        if keep {
+            vals.push(n);
+        } else {
+            null_count += 1;
+        }
+    }
+
+    if ascending {
+        vals.sort_by(f64::total_cmp);
+    } else {
+        vals.sort_by(|a, b| b.total_cmp(a));
+    }
+
+    let mut bldr = Float64Builder::with_capacity(vals.len() + null_count);
+    bldr.append_slice(&vals);
+    bldr.append_nulls(null_count);
+    bldr.finish()
+}
+
+/// Converts the numbers to a sorted array; NaN becomes null and nulls are last.
+///
+/// See [`total_cmp2array`].
+pub fn sorted_num2arr64f<I>(num: I, ascending: bool) -> Float64Array
+where
+    I: Iterator<Item = f64>,
+{
+    total_cmp2array(num, ascending, false)
+}
+
+/// Reinterprets the bit patterns as `f64` values; NaN patterns become nulls.
+pub fn bits2arr64f<I>(num: I) -> Float64Array
+where
+    I: Iterator<Item = u64>,
+{
+    num2arr64f(num.map(f64::from_bits))
+}
+
+/// Reinterprets the bit patterns as `f32` values; NaN patterns become nulls.
+pub fn bits2arr32f<I>(num: I) -> Float32Array
+where
+    I: Iterator<Item = u32>,
+{
+    num2arr32f(num.map(f32::from_bits))
+}
+
+/// The trailing window of [`moving_avg2arr64f`].
+///
+/// The finite values are summed with Neumaier compensation. The sum is rebuilt
+/// from the window once it overflows and after every `len` pops, so neither an
+/// outlier nor the accumulated rounding outlives the window for long.
+#[derive(Default)]
+struct MovingWindow {
+    vals: VecDeque<f64>,
+    sum: f64,
+    compensation: f64,
+    pops: usize,
+    nan_count: usize,
+    pos_inf_count: usize,
+    neg_inf_count: usize,
+}
+
+impl MovingWindow {
+    fn counter(&mut self, v: f64) -> Option<&mut usize> {
+        match v {
+            _ if v.is_nan() => Some(&mut self.nan_count),
+            f64::INFINITY => Some(&mut self.pos_inf_count),
+            f64::NEG_INFINITY => Some(&mut self.neg_inf_count),
+            _ => None,
+        }
+    }
+
+    fn add(&mut self, v: f64) {
+        let t: f64 = self.sum + v;
+        self.compensation += if self.sum.abs() >= v.abs() {
+            (self.sum - t) + v
+        } else {
+            (v - t) + self.sum
+        };
+        self.sum = t;
+    }
+
+    fn resum(&mut self) {
+        self.sum = 0.0;
+        self.compensation = 0.0;
+        self.pops = 0;
+        let vals: VecDeque<f64> = std::mem::take(&mut self.vals);
+        for &v in vals.iter().filter(|v| v.is_finite()) {
+            self.add(v);
+        }
+        self.vals = vals;
+    }
+
+    fn push(&mut self, v: f64) {
+        self.vals.push_back(v);
+        match self.counter(v) {
+            Some(c) => *c += 1,
+            None => self.add(v),
+        }
+    }
+
+    fn pop(&mut self) {
+        let Some(v) = self.vals.pop_front() else {
+            return;
+        };
+        match self.counter(v) {
+            Some(c) => *c -= 1,
+            None => self.add(-v),
+        }
+        self.pops += 1;
+        let stale: bool = self.pops >= self.vals.len() || !self.sum.is_finite();
+        if stale {
+            self.resum();
+        }
+    }
+
+    /// The mean of the finite window (scaled down if the sum overflows).
+    fn finite_mean(&self) -> f64 {
+        let len: f64 = self.vals.len() as f64;
+        let sum: f64 = self.sum + self.compensation;
+        if sum.is_finite() {
+            sum / len
+        } else {
+            self.vals.iter().map(|v| v / len).sum()
+        }
+    }
+
+    /// The mean of the window; `None` if it contains a NaN.
+    fn mean(&self) -> Option<f64> {
+        match (self.nan_count, self.pos_inf_count, self.neg_inf_count) {
+            (0, 0, 0) => Some(self.finite_mean()),
+            (0, 0, _) => Some(f64::NEG_INFINITY),
+            (0, _, 0) => Some(f64::INFINITY),
+            _ => None,
+        }
+    }
+}
+
+/// Converts the numbers to their trailing `window`-element means.
+///
+/// The first `window - 1` slots are nulls (not enough data yet), and so is
+/// every slot whose window contains a NaN (or both `+inf` and `-inf`). The sum
+/// is compensated and periodically rebuilt from the window (amortized O(1) per
+/// slot), so an outlier does not bias later windows. A `window` of 0 is an error.
+pub fn moving_avg2arr64f<I>(num: I, window: usize) -> Result<Float64Array, InvalidParamError>
+where
+    I: Iterator<Item = f64>,
+{
+    if window == 0 {
+        return Err(InvalidParamError { name: "window" });
+    }
+    let mut bldr = Float64Builder::with_capacity(CAPACITY_DEFAULT);
+    let mut win = MovingWindow::default();
+
+    for n in num {
+        win.push(n);
+        if win.vals.len() > window {
+            win.pop();
+        }
+        let full: bool = win.vals.len() == window;
+        bldr.append_option(full.then(|| win.mean()).flatten());
+    }
+
+    Ok(bldr.finish())
+}
+
+/// An index is not less than the length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexOutOfRangeError {
+    pub index: usize,
+    pub len: usize,
+}
+
+impl std::fmt::Display for IndexOutOfRangeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "index {} out of range for length {}",
+            self.index, self.len
+        )
+    }
+}
+
+impl std::error::Error for IndexOutOfRangeError {}
+
+/// Converts the `(index, value)` pairs to an array of `len` slots.
+///
+/// Slots without a pair are nulls, NaN values are nulls, and a later pair for
+/// the same index overwrites an earlier one. An index `>= len` is an error.
+pub fn sparse2arr64f<I>(pairs: I, len: usize) -> Result<Float64Array, IndexOutOfRangeError>
+where
+    I: Iterator<Item = (usize, f64)>,
+{
+    let mut values: Vec<f64> = vec![0.0; len];
+    let mut valid: Vec<bool> = vec![false; len];
+
+    for (index, v) in pairs {
+        if index >= len {
+            return Err(IndexOutOfRangeError { index, len });
+        }
+        values[index] = v;
+        valid[index] = !v.is_nan();
+    }
+
+    let nulls = NullBuffer::from(valid);
+    Ok(PrimitiveArray::new(ScalarBuffer::from(values), Some(nulls)))
+}
+
+/// Converts the numbers to an array and computes the `q`-quantile of the non-NaN
+/// values.
+///
+/// The quantile is exact: the non-NaN values are buffered and sorted, and the
+/// result interpolates linearly between the closest ranks (as numpy's default).
+/// This costs one extra copy of the values. The quantile is `None` for an empty
+/// or all-NaN input; a `q` outside of `[0, 1]` is an error.
+pub fn num2array_with_quantile<I>(
+    num: I,
+    q: f64,
+) -> Result<(Float64Array, Option<f64>), InvalidParamError>
+where
+    I: Iterator<Item = f64>,
+{
+    if !(0.0..=1.0).contains(&q) {
+        return Err(InvalidParamError { name: "q" });
+    }
+    let mut bldr = Float64Builder::with_capacity(CAPACITY_DEFAULT);
+    let mut sorted: Vec<f64> = Vec::new();
+
+    for n in num {
+        let o: Option<f64> = nan2none::<arrow::datatypes::Float64Type>(n);
+        sorted.extend(o);
+        bldr.append_option(o);
+    }
+
+    sorted.sort_by(f64::total_cmp);
+    let quantile: Option<f64> = (!sorted.is_empty()).then(|| {
+        let rank: f64 = q * (sorted.len() - 1) as f64;
+        let lo: usize = rank.floor() as usize;
+        let hi: usize = rank.ceil() as usize;
+        let frac: f64 = rank - lo as f64;
+        sorted[lo] + (sorted[hi] - sorted[lo]) * frac
+    });
+
+    Ok((bldr.finish(), quantile))
+}
+
+/// Converts the numbers to an array, replacing repeats of the previous kept
+/// value with nulls (for forward filling).
+///
+/// Values are compared with `==`. NaN becomes a null and does not reset the
+/// previous kept value.
+pub fn dedup_runs2arr64f<I>(num: I) -> Float64Array
+where
+    I: Iterator<Item = f64>,
+{
+    let mut bldr = Float64Builder::with_capacity(CAPACITY_DEFAULT);
+    let mut prev: Option<f64> = None;
+    for n in num {
+        let repeat: bool = n.is_nan() || prev == Some(n);
+        if repeat {
+            bldr.append_null();
+        } else {
+            bldr.append_value(n);
+            prev = Some(n);
+        }
+    }
+    bldr.finish()
+}
+
+/// Converts the durations to fractional seconds.
+pub fn durations2arr64f<I>(num: I) -> Float64Array
+where
+    I: Iterator<Item = Duration>,
+{
+    num2array_default(num.map(|d| d.as_secs_f64()))
+}
+
+/// Converts the optional durations to fractional seconds; `None` becomes null.
+pub fn durations2arr64f_opt<I>(num: I) -> Float64Array
+where
+    I: Iterator<Item = Option<Duration>>,
+{
+    opt2array_default(num.map(|o| o.map(|d| d.as_secs_f64())))
+}
+
+/// Converts the numbers to an array, replacing out-of-order values with nulls.
+///
+/// A value below the previous kept value (or, if `strict`, not above it) becomes
+/// a null. NaN becomes a null and does not change the previous kept value.
+pub fn monotone2arr64f<I>(num: I, strict: bool) -> Float64Array
+where
+    I: Iterator<Item = f64>,
+{
+    let mut bldr = Float64Builder::with_capacity(CAPACITY_DEFAULT);
+    let mut prev: Option<f64> = None;
+    for n in num {
+        let ordered: bool = match prev {
+            None => true,
+            Some(p) if strict => n > p,
+            Some(p) => n >= p,
+        };
+        if n.is_nan() || !ordered {
+            bldr.append_null();
+        } else {
+            bldr.append_value(n);
+            prev = Some(n);
+        }
+    }
+    bldr.finish()
+}
+
+/// Converts the number to a scalar (null if NaN).
+pub fn num2scalar64f(n: f64) -> Scalar<Float64Array> {
+    let arr: Float64Array = num2array(std::iter::once(n), 1);
+    Scalar::new(arr)
+}
+
+/// Checks if the value is representable in `T` without loss.
+///
+/// The comparison is bitwise, so `-0.0` must stay `-0.0`. NaN always fits: it
+/// becomes a NaN of the narrower width (the payload may not survive).
+pub fn fits_exactly<T>(val: f64) -> bool
+where
+    T: FloatType,
+{
+    T::fits_exactly(val)
+}
+
+/// Narrows the value to `f32`.
+///
+/// Returns `None` for NaN and for finite values which overflow to infinity;
+/// infinities stay infinite.
+pub fn f64_to_f32(v: f64) -> Option<f32> {
+    let f: f32 = v as f32;
+    let overflow: bool = f.is_infinite() && v.is_finite();
+    let ok: bool = !(v.is_nan() || overflow);
+    ok.then_some(f)
+}
+
+/// Narrows the values to `f32`; NaN and overflows become nulls.
+pub fn f64_iter2arr32f<I>(num: I) -> Float32Array
+where
+    I: Iterator<Item = f64>,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(CAPACITY_DEFAULT);
+
+    for n in num {
+        bldr.append_option(f64_to_f32(n));
+    }
+
+    bldr.finish()
+}
+
+/// Narrows the optional values to `f32`; `None`, NaN and overflows become nulls.
+pub fn opt_f64_iter2arr32f<I>(num: I) -> Float32Array
+where
+    I: Iterator<Item = Option<f64>>,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(CAPACITY_DEFAULT);
+
+    for o in num {
+        bldr.append_option(o.and_then(f64_to_f32));
+    }
+
+    bldr.finish()
+}
+
+/// Narrows the optional values to `f16`; `None`, NaN and overflows become nulls.
+///
+/// The values are rounded once, directly from `f64`.
+pub fn opt_f64_iter2arr16f<I>(num: I) -> Float16Array
+where
+    I: Iterator<Item = Option<f64>>,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(CAPACITY_DEFAULT);
+
+    for o in num {
+        bldr.append_option(o.and_then(f64_to_native::<arrow::datatypes::Float16Type>));
+    }
+
+    bldr.finish()
+}
+
+/// Narrows the value to `f16`.
+///
+/// Returns `None` for NaN and for finite values which overflow to infinity;
+/// infinities stay infinite.
+pub fn f32_to_f16(v: f32) -> Option<half::f16> {
+    let f: half::f16 = half::f16::from_f32(v);
+    let overflow: bool = f.is_infinite() && v.is_finite();
+    let ok: bool = !(v.is_nan() || overflow);
+    ok.then_some(f)
+}
+
+/// Narrows the values to `f16`; NaN and overflows become nulls.
+pub fn f32_iter2arr16f<I>(num: I) -> Float16Array
+where
+    I: Iterator<Item = f32>,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(CAPACITY_DEFAULT);
+
+    for n in num {
+        bldr.append_option(f32_to_f16(n));
+    }
+
+    bldr.finish()
+}
+
+/// Converts the results to an array, returning the first error.
+///
+/// The capacity comes from the lower bound of the size hint.
+pub fn try_result2arr64f<I, E>(num: I) -> Result<Float64Array, E>
+where
+    I: Iterator<Item = Result<f64, E>>,
+{
+    let cap: usize = num.size_hint().0;
+    let mut bldr = PrimitiveBuilder::with_capacity(cap);
+
+    for r in num {
+        num2builder(r?, &mut bldr);
+    }
+
+    Ok(bldr.finish())
+}
+
+/// Parses the strings to an array; parse failures (including empty strings) and
+/// NaN become nulls.
+pub fn str2arr64f<'a, I>(num: I) -> Float64Array
+where
+    I: Iterator<Item = &'a str>,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(CAPACITY_DEFAULT);
+
+    for s in num {
+        let o: Option<f64> = s.parse::<f64>().ok();
+        opt2builder(o, &mut bldr);
+    }
+
+    bldr.finish()
+}
+
+/// A string which could not be parsed as a number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrParseError {
+    /// The position of the string.
+    pub index: usize,
+
+    /// The offending string.
+    pub value: String,
+}
+
+impl std::fmt::Display for StrParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid number at {}: {:?}", self.index, self.value)
+    }
+}
+
+impl std::error::Error for StrParseError {}
+
+/// Parses the strings to an array, rejecting unparsable strings; NaN becomes null.
+pub fn try_str2arr64f<'a, I>(num: I) -> Result<Float64Array, StrParseError>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(CAPACITY_DEFAULT);
+
+    for (index, s) in num.enumerate() {
+        let n: f64 = s.parse::<f64>().map_err(|_| StrParseError {
+            index,
+            value: s.into(),
+        })?;
+        num2builder(n, &mut bldr);
+    }
+
+    Ok(bldr.finish())
+}
+
+/// The byte length is not a multiple of the element width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LenError {
+    /// The number of bytes given.
+    pub len: usize,
+
+    /// The size of an element in bytes.
+    pub width: usize,
+}
+
+impl std::fmt::Display for LenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "byte length {} is not a multiple of {}",
+            self.len, self.width
+        )
+    }
+}
+
+impl std::error::Error for LenError {}
+
+/// The byte order of packed values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    /// Least significant byte first.
+    Little,
+
+    /// Most significant byte first.
+    Big,
+
+    /// The byte order of the target.
+    Native,
+}
+
+/// Decodes the packed values of `T` in the given byte order; NaN becomes null.
+pub fn bytes2array<T>(bytes: &[u8], endian: Endianness) -> Result<PrimitiveArray<T>, LenError>
+where
+    T: FloatType,
+{
+    let width: usize = std::mem::size_of::<T::Native>();
+    let chunks = bytes.chunks_exact(width);
+    if !chunks.remainder().is_empty() {
+        return Err(LenError {
+            len: bytes.len(),
+            width,
+        });
+    }
+    let reverse: bool = match endian {
+        Endianness::Little => false,
+        Endianness::Big => true,
+        Endianness::Native => cfg!(target_endian = "big"),
+    };
+    let cap: usize = chunks.len();
+    let nums = chunks.map(|c| {
+        let mut buf = [0u8; 8];
+        let le: &mut [u8] = &mut buf[..width];
+        le.copy_from_slice(c);
+        if reverse {
+            le.reverse();
+        }
+        T::from_le_slice(le)
+    });
+    Ok(num2array(nums, cap))
+}
+
+/// Decodes the packed little-endian `f32` values; NaN becomes null.
+pub fn le_bytes2arr32f(bytes: &[u8]) -> Result<Float32Array, LenError> {
+    bytes2array(bytes, Endianness::Little)
+}
+
+/// Decodes the packed big-endian `f32` values; NaN becomes null.
+pub fn be_bytes2arr32f(bytes: &[u8]) -> Result<Float32Array, LenError> {
+    bytes2array(bytes, Endianness::Big)
+}
+
+/// Reads little-endian `f64` values from the reader until EOF; NaN becomes null.
+///
+/// A trailing partial value (EOF in the middle of 8 bytes) is an
+/// [`std::io::ErrorKind::UnexpectedEof`] error.
+pub fn read_le_f64_array<R>(mut r: R, cap: usize) -> Result<Float64Array, std::io::Error>
+where
+    R: std::io::Read,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(cap);
+    let mut buf: [u8; 8] = [0; 8];
+
+    loop {
+        let mut filled: usize = 0;
+        while filled < buf.len() {
+            match r.read(&mut buf[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        match filled {
+            0 => break,
+            8 => num2builder(f64::from_le_bytes(buf), &mut bldr),
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    format!("partial f64: {filled} trailing bytes"),
+                ));
+            }
+        }
+    }
+
+    Ok(bldr.finish())
+}
+
+/// Converts the `f64` to the native type of `T`.
+///
+/// Returns `None` for NaN and for finite values which overflow to infinity.
+pub fn f64_to_native<T>(v: f64) -> Option<T::Native>
+where
+    T: FloatType,
+{
+    let n: T::Native = T::from_f64(v);
+    let overflow: bool = is_infinite::<T>(n) && v.is_finite();
+    let ok: bool = !(v.is_nan() || overflow);
+    ok.then_some(n)
+}
+
+/// Casts the array to another float width through `f64`.
+///
+/// Nulls are preserved; NaN and finite values which overflow the target width
+/// become nulls (infinities stay infinite).
+pub fn cast_float<F, T>(arr: &PrimitiveArray<F>) -> PrimitiveArray<T>
+where
+    F: FloatType,
+    T: FloatType,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(arr.len());
+
+    for o in arr.iter() {
+        bldr.append_option(o.and_then(|v| f64_to_native::<T>(F::to_f64(v))));
+    }
+
+    bldr.finish()
+}
+
+/// Converts the json number to the native type of `T`; overflows become `None`.
+///
+/// With the `arbitrary_precision` feature, `as_f64` already parses the textual
+/// form and yields `None` for numbers beyond the `f64` range.
+#[cfg(feature = "json")]
+pub fn num2opt_as<T>(n: &serde_json::Number) -> Option<T::Native>
+where
+    T: FloatType,
+{
+    n.as_f64().and_then(f64_to_native::<T>)
+}
+
+/// Converts the json value to the native type of `T`; non-numbers become `None`.
+#[cfg(feature = "json")]
+pub fn val2opt_as<T>(v: &serde_json::Value) -> Option<T::Native>
+where
+    T: FloatType,
+{
+    match v {
+        serde_json::Value::Number(n) => num2opt_as::<T>(n),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "json")]
+pub fn num2opt(n: &serde_json::Number) -> Option<f64> {
+    num2opt_as::<arrow::datatypes::Float64Type>(n)
+}
+
+/// Narrows the json number to `f32`; NaN and overflows beyond the `f32` range
+/// become `None` (precision loss is not detected).
+#[cfg(feature = "json")]
+pub fn num2opt_f32(n: &serde_json::Number) -> Option<f32> {
+    num2opt_as::<arrow::datatypes::Float32Type>(n)
+}
+
+#[cfg(feature = "json")]
+pub fn val2opt(v: &serde_json::Value) -> Option<f64> {
+    val2opt_as::<arrow::datatypes::Float64Type>(v)
+}
+
+/// Converts the array to a json array; nulls become json nulls.
+///
+/// Json cannot represent NaN or infinities, so such valid values (e.g., kept
+/// by [`NanPolicy::Keep`]) become json nulls as well.
+#[cfg(feature = "json")]
+pub fn arr64f2json(arr: &Float64Array) -> serde_json::Value {
+    let items: Vec<serde_json::Value> = arr
+        .iter()
+        .map(|o| {
+            o.and_then(serde_json::Number::from_f64)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null)
+        })
+        .collect();
+    serde_json::Value::Array(items)
+}
+
+/// Same as [`val2opt`], but also parses numeric strings like `"3.14"`.
+///
+/// Strings which fail to parse, and `"NaN"`/`"Infinity"` strings, become `None`.
+#[cfg(feature = "json")]
+pub fn val2opt_lenient(v: &serde_json::Value) -> Option<f64> {
+    match v {
+        serde_json::Value::String(s) => s
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .and_then(nonfinite2none::<arrow::datatypes::Float64Type>),
+        _ => val2opt(v),
+    }
+}
+
+/// Same as [`val2opt`], but also converts booleans: `true` is `1.0`, `false` is `0.0`.
+#[cfg(feature = "json")]
+pub fn val2opt_bool_numeric(v: &serde_json::Value) -> Option<f64> {
+    match v {
+        serde_json::Value::Bool(b) => Some(f64::from(u8::from(*b))),
+        _ => val2opt(v),
+    }
+}
+
+/// The json value was expected to be an array.
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotAnArrayError;
+
+#[cfg(feature = "json")]
+impl std::fmt::Display for NotAnArrayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("not a json array")
+    }
+}
+
+#[cfg(feature = "json")]
+impl std::error::Error for NotAnArrayError {}
+
+/// Converts the json array to an array; non-numbers become nulls.
+#[cfg(feature = "json")]
+pub fn json_array2arr64f(v: &serde_json::Value) -> Result<Float64Array, NotAnArrayError> {
+    let serde_json::Value::Array(items) = v else {
+        return Err(NotAnArrayError);
+    };
+    Ok(opt2array(items.iter().map(val2opt), items.len()))
+}
+
+/// The default nesting limit of [`json_flatten2arr64f`].
+#[cfg(feature = "json")]
+pub const FLATTEN_DEPTH_DEFAULT: usize = 64;
+
+#[cfg(feature = "json")]
+fn json_flatten2builder(v: &serde_json::Value, depth: usize, bldr: &mut Float64Builder) {
+    match v {
+        serde_json::Value::Array(items) if depth > 0 => {
+            for item in items {
+                json_flatten2builder(item, depth - 1, bldr);
+            }
+        }
+        _ => bldr.append_option(val2opt(v)),
+    }
+}
+
+/// Flattens the nested json arrays into an array; non-numbers become nulls.
+///
+/// A top-level scalar yields a single-element array. Arrays nested deeper than
+/// `max_depth` are not walked; each becomes a single null.
+#[cfg(feature = "json")]
+pub fn json_flatten2arr64f_with_depth(v: &serde_json::Value, max_depth: usize) -> Float64Array {
+    let mut bldr = Float64Builder::with_capacity(CAPACITY_DEFAULT);
+    json_flatten2builder(v, max_depth, &mut bldr);
+    bldr.finish()
+}
+
+/// Same as [`json_flatten2arr64f_with_depth`] with [`FLATTEN_DEPTH_DEFAULT`].
+#[cfg(feature = "json")]
+pub fn json_flatten2arr64f(v: &serde_json::Value) -> Float64Array {
+    json_flatten2arr64f_with_depth(v, FLATTEN_DEPTH_DEFAULT)
+}
+
+/// A float array which (de)serializes as a sequence of `number | null`.
+///
+/// Nulls, NaN and infinities serialize as null; see [`arr64f2json`].
+/// Deserialization goes through [`val2opt_as`], so non-numbers and numbers
+/// which overflow the width become nulls.
+#[cfg(feature = "serde")]
+pub struct FloatColumn<T>(pub PrimitiveArray<T>)
+where
+    T: FloatType;
+
+#[cfg(feature = "serde")]
+impl<T> Clone for FloatColumn<T>
+where
+    T: FloatType,
+{
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> std::fmt::Debug for FloatColumn<T>
+where
+    T: FloatType,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("FloatColumn").field(&self.0).finish()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> PartialEq for FloatColumn<T>
+where
+    T: FloatType,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for FloatColumn<T>
+where
+    T: FloatType,
+{
+    fn serialize<S>(&self, ser: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        ser.collect_seq(
+            self.0
+                .iter()
+                .map(|o| NativeValue::<T>(o.and_then(nonfinite2none::<T>))),
+        )
+    }
+}
+
+/// A value serialized through [`FloatType::serialize_native`] (`None` as null).
+#[cfg(feature = "serde")]
+struct NativeValue<T>(Option<T::Native>)
+where
+    T: FloatType;
+
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for NativeValue<T>
+where
+    T: FloatType,
+{
+    fn serialize<S>(&self, ser: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self.0 {
+            None => ser.serialize_none(),
+            Some(v) => T::serialize_native(v, ser),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for FloatColumn<T>
+where
+    T: FloatType,
+{
+    fn deserialize<D>(de: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let vals: Vec<serde_json::Value> = serde::Deserialize::deserialize(de)?;
+        let cap: usize = vals.len();
+        Ok(Self(opt2array(vals.iter().map(val2opt_as::<T>), cap)))
+    }
+}
+
+/// Converts the json values to an array; non-numbers become nulls.
+#[cfg(feature = "json")]
+pub fn values2arr64f<I>(vals: I) -> Float64Array
+where
+    I: Iterator<Item = serde_json::Value>,
+{
+    opt2array_default(vals.map(|v| val2opt(&v)))
+}
+
+/// Converts the json values to a half precision array.
+///
+/// Non-numbers become nulls, as do numbers beyond the `f16` range (instead of
+/// infinities).
+#[cfg(feature = "json")]
+pub fn json_values2arr16f<I>(vals: I) -> Float16Array
+where
+    I: Iterator<Item = serde_json::Value>,
+{
+    let narrowed =
+        vals.map(|v| val2opt(&v).and_then(f64_to_native::<arrow::datatypes::Float16Type>));
+    opt2array_default(narrowed)
+}
+
+/// A json value which is neither a number nor null.
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionError {
+    /// The position of the value.
+    pub index: usize,
+
+    /// The offending value.
+    pub value: serde_json::Value,
+}
+
+#[cfg(feature = "json")]
+impl std::fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "not a number at {}: {}", self.index, self.value)
+    }
+}
+
+#[cfg(feature = "json")]
+impl std::error::Error for ConversionError {}
+
+/// Converts the json values to an array, rejecting non-numbers.
+///
+/// Json nulls become nulls; any other non-number value is an error.
+#[cfg(feature = "json")]
+pub fn try_values2arr64f<I>(vals: I) -> Result<Float64Array, ConversionError>
+where
+    I: Iterator<Item = serde_json::Value>,
+{
+    let mut bldr = PrimitiveBuilder::with_capacity(CAPACITY_DEFAULT);
+
+    for (index, v) in vals.enumerate() {
+        match v {
+            serde_json::Value::Null => bldr.append_null(),
+            serde_json::Value::Number(n) => bldr.append_option(num2opt(&n)),
+            value => return Err(ConversionError { index, value }),
+        }
+    }
+
+    Ok(bldr.finish())
+}
+
+#[cfg(feature = "json")]
+fn ndjson2arr64f_with<R>(r: R, lenient: bool) -> Result<Float64Array, std::io::Error>
+where
+    R: std::io::Read,
+{
+    let rdr = std::io::BufReader::new(r);
+    let mut bldr = PrimitiveBuilder::with_capacity(CAPACITY_DEFAULT);
+
+    for rline in rdr.lines() {
+        let line: String = rline?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let parsed: Result<serde_json::Value, _> = serde_json::from_str(&line);
+        match (parsed, lenient) {
+            (Ok(v), _) => bldr.append_option(val2opt(&v)),
+            (Err(_), true) => bldr.append_null(),
+            (Err(e), false) => return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+        }
+    }
+
+    Ok(bldr.finish())
+}
+
+/// Reads the newline-delimited json numbers into an array.
+///
+/// Blank lines are skipped; lines which are not valid json become nulls.
+#[cfg(feature = "json")]
+pub fn ndjson2arr64f<R>(r: R) -> Result<Float64Array, std::io::Error>
+where
+    R: std::io::Read,
+{
+    ndjson2arr64f_with(r, true)
+}
+
+/// Reads the newline-delimited json numbers into an array.
+///
+/// Same as [`ndjson2arr64f`], but a line which is not valid json is an
+/// [`std::io::ErrorKind::InvalidData`] error.
+#[cfg(feature = "json")]
+pub fn ndjson2arr64f_strict<R>(r: R) -> Result<Float64Array, std::io::Error>
+where
+    R: std::io::Read,
+{
+    ndjson2arr64f_with(r, false)
+}
+
+/// Converts the slice to an array using the rayon thread pool.
+///
+/// Same as [`slice2array`]: NaN becomes null and no null buffer is built if
+/// the slice has no NaN.
+#[cfg(feature = "rayon")]
+pub fn par_slice2arr64f(data: &[f64]) -> Float64Array {
+    use arrow::buffer::Buffer;
+    use rayon::prelude::*;
+
+    // A multiple of 64 so that each chunk fills whole validity words.
+    const CHUNK: usize = 64 * 1024;
+
+    let mut values: Vec<f64> = vec![0.0; data.len()];
+    let mut words: Vec<u64> = vec![0; data.len().div_ceil(64)];
+    values
+        .par_chunks_mut(CHUNK)
+        .zip(words.par_chunks_mut(CHUNK / 64))
+        .zip(data.par_chunks(CHUNK))
+        .for_each(|((vals, wrds), src)| {
+            vals.copy_from_slice(src);
+            for (w, bits) in wrds.iter_mut().zip(src.chunks(64)) {
+                *w = bits
+                    .iter()
+                    .enumerate()
+                    .fold(0u64, |acc, (i, v)| acc | (u64::from(!v.is_nan()) << i));
+            }
+        });
+
+    let valid = BooleanBuffer::new(Buffer::from_vec(words), 0, data.len());
+    let nulls: Option<NullBuffer> = Some(NullBuffer::new(valid)).filter(|n| n.null_count() > 0);
+    PrimitiveArray::new(ScalarBuffer::from(values), nulls)
+}
+
+/// Drains the stream into an array; NaN becomes null.
+#[cfg(feature = "async")]
+pub async fn stream2arr64f<S>(mut stream: S, cap: usize) -> Float64Array
+where
+    S: futures::Stream<Item = f64> + Unpin,
+{
+    use futures::StreamExt;
+
+    let mut bldr = PrimitiveBuilder::with_capacity(cap);
+
+    while let Some(n) = stream.next().await {
+        num2builder(n, &mut bldr);
+    }
+
+    bldr.finish()
+}
+
+/// Converts the stream to arrays of at most `chunk_size` elements.
+///
+/// The last array may be shorter; an empty stream yields no arrays.
+///
+/// # Panics
+///
+/// Panics if `chunk_size` is 0.
+#[cfg(feature = "async")]
+pub fn stream2chunks<S>(stream: S, chunk_size: usize) -> impl futures::Stream<Item = Float64Array>
+where
+    S: futures::Stream<Item = f64> + Unpin,
+{
+    use futures::StreamExt;
+
+    assert!(chunk_size > 0, "chunk_size must be positive");
+    futures::stream::unfold(stream, move |mut st| async move {
+        let mut bldr = PrimitiveBuilder::with_capacity(chunk_size);
+        while bldr.len() < chunk_size {
+            let Some(n) = st.next().await else {
+                break;
+            };
+            num2builder(n, &mut bldr);
+        }
+        let empty: bool = bldr.is_empty();
+        let ok: bool = !empty;
+        ok.then(|| (bldr.finish(), st))
+    })
+}
+
+/// Converts the 1-D ndarray (possibly a strided view) to an array; NaN becomes null.
+#[cfg(feature = "ndarray")]
+pub fn ndarray2arr64f<S>(a: &ndarray::ArrayBase<S, ndarray::Ix1>) -> Float64Array
+where
+    S: ndarray::Data<Elem = f64>,
+{
+    num2array(a.iter().copied(), a.len())
+}
+
+/// Converts the 1-D ndarray (possibly a strided view) to an array; NaN becomes null.
+#[cfg(feature = "ndarray")]
+pub fn ndarray2arr32f<S>(a: &ndarray::ArrayBase<S, ndarray::Ix1>) -> Float32Array
+where
+    S: ndarray::Data<Elem = f32>,
+{
+    num2array(a.iter().copied(), a.len())
+}
+
+/// Converts the array to a 1-D ndarray; nulls become NaN.
+#[cfg(feature = "ndarray")]
+pub fn arr64f2ndarray(arr: &Float64Array) -> ndarray::Array1<f64> {
+    ndarray::Array1::from(array2vec_nan(arr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::datatypes::Float32Type;
+    use arrow::datatypes::Float64Type;
+
+    #[test]
+    fn test_num2arr32f_basic() {
+        // 1.0, NaN, 3.5
+        let data = vec![1.0f32, f32::NAN, 3.5f32];
+        let arr: Float32Array = num2arr32f(data.into_iter());
+
+        assert_eq!(arr.len(), 3);
+        assert_eq!(arr.null_count(), 1);
+
+        // Valid indices
+        assert!(arr.is_valid(0));
+        assert!(!arr.is_valid(1)); // NaN should become null
+        assert!(arr.is_valid(2));
+
+        // Values
+        assert_eq!(arr.value(0), 1.0);
+        assert_eq!(arr.value(2), 3.5);
+    }
+
+    #[test]
+    fn test_num2arr32f_empty_and_all_nan() {
         // Empty iterator
         let empty_arr: Float32Array = num2arr32f(std::iter::empty());
         assert_eq!(empty_arr.len(), 0);
         assert_eq!(empty_arr.null_count(), 0);
 
-        // All NaN
-        let nan_vec = vec![f32::NAN; 5];
-        let nan_arr: Float32Array = num2arr32f(nan_vec.into_iter());
-        assert_eq!(nan_arr.len(), 5);
-        assert_eq!(nan_arr.null_count(), 5);
-        for i in 0..5 {
-            assert!(!nan_arr.is_valid(i));
+        // All NaN
+        let nan_vec = vec![f32::NAN; 5];
+        let nan_arr: Float32Array = num2arr32f(nan_vec.into_iter());
+        assert_eq!(nan_arr.len(), 5);
+        assert_eq!(nan_arr.null_count(), 5);
+        for i in 0..5 {
+            assert!(!nan_arr.is_valid(i));
+        }
+    }
+
+    #[test]
+    fn test_num2builder_with_policies() {
+        let mut bldr: PrimitiveBuilder<Float32Type> = PrimitiveBuilder::new();
+        num2builder_with(f32::NAN, NanPolicy::ToNull, &mut bldr);
+        num2builder_with(f32::NAN, NanPolicy::Keep, &mut bldr);
+        num2builder_with(f32::NAN, NanPolicy::Replace(0.0), &mut bldr);
+        num2builder_with(2.0, NanPolicy::Replace(0.0), &mut bldr);
+        let arr: Float32Array = bldr.finish();
+
+        assert_eq!(arr.len(), 4);
+        assert_eq!(arr.null_count(), 1);
+        assert!(!arr.is_valid(0));
+        assert!(arr.is_valid(1)); // Keep appends a value
+        assert!(arr.value(1).is_nan());
+        assert_eq!(arr.value(2), 0.0);
+        assert_eq!(arr.value(3), 2.0);
+
+        let dflt: NanPolicy<Float32Type> = NanPolicy::default();
+        assert!(matches!(dflt, NanPolicy::ToNull));
+    }
+
+    #[test]
+    fn test_num2array_finite() {
+        let data = vec![1.0f32, f32::INFINITY, f32::NAN, f32::NEG_INFINITY, -2.0];
+        let arr: Float32Array = num2array_finite(data.into_iter(), 5);
+
+        assert_eq!(arr.len(), 5);
+        assert_eq!(arr.null_count(), 3);
+        assert_eq!(arr.value(0), 1.0);
+        assert_eq!(arr.value(4), -2.0);
+
+        assert_eq!(inf2none::<Float32Type>(f32::INFINITY), None);
+        assert!(inf2none::<Float32Type>(f32::NAN).is_some());
+    }
+
+    #[test]
+    fn test_num2array_exact() {
+        let data = vec![1.0f32, f32::NAN, 3.0];
+        let arr: Float32Array = num2array_exact(data.into_iter());
+        assert_eq!(arr.len(), 3);
+        assert_eq!(arr.null_count(), 1);
+
+        let opts = vec![Some(1.0f32), None, Some(f32::NAN), Some(4.0)];
+        let arr: Float32Array = opt2array_exact(opts.into_iter());
+        assert_eq!(arr.len(), 4);
+        assert_eq!(arr.null_count(), 2);
+    }
+
+    #[test]
+    fn test_slice2array() {
+        let data = [1.0f32, f32::NAN, 3.5];
+        let arr: Float32Array = slice2array(&data);
+        let expected: Float32Array = num2arr32f(data.iter().copied());
+        assert_eq!(arr, expected);
+
+        let clean: Float32Array = slice2array::<Float32Type>(&[1.0, 2.0]);
+        assert_eq!(clean.null_count(), 0);
+        assert!(clean.nulls().is_none());
+    }
+
+    #[test]
+    fn test_array2batch() {
+        let arr: Float32Array = num2arr32f(vec![1.0f32, f32::NAN].into_iter());
+        let batch: RecordBatch = array2batch(arr, "x").unwrap();
+
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.num_columns(), 1);
+        let field = batch.schema().field(0).clone();
+        assert_eq!(field.name(), "x");
+        assert!(field.is_nullable());
+        assert_eq!(field.data_type(), &arrow::datatypes::DataType::Float32);
+        assert_eq!(batch.column(0).null_count(), 1);
+    }
+
+    #[test]
+    fn test_columns2struct() {
+        let xs: Float32Array = num2arr32f(vec![1.0f32, 2.0].into_iter());
+        let ys: Float32Array = num2arr32f(vec![f32::NAN, 4.0].into_iter());
+        let sa: StructArray = columns2struct(&[("x", xs.clone()), ("y", ys)]).unwrap();
+        assert_eq!(sa.len(), 2);
+        assert_eq!(sa.num_columns(), 2);
+        assert_eq!(sa.column_by_name("y").unwrap().null_count(), 1);
+
+        let short: Float32Array = num2arr32f(vec![1.0f32].into_iter());
+        let err = columns2struct(&[("x", xs), ("z", short)]).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("column z has length 1, expected 2")
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_values2arr64f() {
+        let vals = vec![
+            serde_json::json!(1.5),
+            serde_json::json!(null),
+            serde_json::json!("2"),
+            serde_json::json!(3),
+        ];
+        let arr: Float64Array = values2arr64f(vals.into_iter());
+        assert_eq!(arr.len(), 4);
+        assert_eq!(arr.null_count(), 2);
+        assert_eq!(arr.value(0), 1.5);
+        assert_eq!(arr.value(3), 3.0);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_array2arr64f() {
+        let v = serde_json::json!([1.0, "x", null, 2]);
+        let arr: Float64Array = json_array2arr64f(&v).unwrap();
+        assert_eq!(arr.len(), 4);
+        assert_eq!(arr.null_count(), 2);
+
+        let empty: Float64Array = json_array2arr64f(&serde_json::json!([])).unwrap();
+        assert_eq!(empty.len(), 0);
+        assert_eq!(empty.null_count(), 0);
+
+        assert_eq!(
+            json_array2arr64f(&serde_json::json!(1.0)),
+            Err(NotAnArrayError)
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_ndjson2arr64f() {
+        let input: &[u8] = b"1.5\n\"x\"\n{bad\n\n2\n";
+        let arr: Float64Array = ndjson2arr64f(input).unwrap();
+        assert_eq!(arr.len(), 4);
+        assert_eq!(arr.null_count(), 2);
+        assert_eq!(arr.value(0), 1.5);
+        assert_eq!(arr.value(3), 2.0);
+
+        let e = ndjson2arr64f_strict(input).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_try_values2arr64f() {
+        let ok = vec![serde_json::json!(1.0), serde_json::json!(null)];
+        let arr: Float64Array = try_values2arr64f(ok.into_iter()).unwrap();
+        assert_eq!(arr.len(), 2);
+        assert_eq!(arr.null_count(), 1);
+
+        let bad = vec![serde_json::json!(1.0), serde_json::json!("2.0")];
+        let e: ConversionError = try_values2arr64f(bad.into_iter()).unwrap_err();
+        assert_eq!(e.index, 1);
+        assert_eq!(e.value, serde_json::json!("2.0"));
+    }
+
+    #[test]
+    fn test_num2chunks() {
+        let data = vec![1.0f32, f32::NAN, 3.0, 4.0, 5.0];
+        let chunks: Vec<Float32Array> = num2chunks(data.into_iter(), 2).collect();
+        let lens: Vec<usize> = chunks.iter().map(|a| a.len()).collect();
+        assert_eq!(lens, vec![2, 2, 1]);
+        assert_eq!(chunks[0].null_count(), 1);
+
+        let none: Vec<Float32Array> = num2chunks(std::iter::empty(), 3).collect();
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_num2chunks_zero() {
+        let _ = num2chunks::<_, Float32Type>(std::iter::once(1.0f32), 0);
+    }
+
+    #[test]
+    fn test_int2arr32i() {
+        let arr: arrow::array::Int32Array = int2arr32i(vec![1, -2, 3].into_iter());
+        assert_eq!(arr.len(), 3);
+        assert_eq!(arr.null_count(), 0);
+        assert_eq!(arr.value(1), -2);
+    }
+
+    #[test]
+    fn test_array2vec() {
+        let arr: Float32Array = num2arr32f(vec![1.0f32, f32::NAN, 3.0].into_iter());
+        assert_eq!(array2vec(&arr), vec![Some(1.0), None, Some(3.0)]);
+
+        let dense: Vec<f32> = array2vec_nan(&arr);
+        assert_eq!(dense.len(), 3);
+        assert_eq!(dense[0], 1.0);
+        assert!(dense[1].is_nan());
+
+        let empty: Float32Array = num2arr32f(std::iter::empty());
+        assert!(array2vec(&empty).is_empty());
+        assert!(array2vec_nan(&empty).is_empty());
+    }
+
+    #[test]
+    fn test_float_array_builder() {
+        let mut fb: FloatArrayBuilder<Float32Type> =
+            FloatArrayBuilder::with_capacity(4).with_nan_policy(NanPolicy::Replace(-1.0));
+        fb.push(1.0);
+        fb.push(f32::NAN);
+        fb.push_opt(None);
+        fb.extend(vec![2.0, 3.0].into_iter());
+        assert_eq!(fb.len(), 5);
+
+        let arr: Float32Array = fb.finish();
+        assert_eq!(arr.len(), 5);
+        assert_eq!(arr.null_count(), 1);
+        assert_eq!(arr.value(1), -1.0);
+        assert!(fb.is_empty());
+
+        fb.push(4.0);
+        fb.reset();
+        fb.push(5.0);
+        let arr: Float32Array = fb.finish();
+        assert_eq!(array2vec(&arr), vec![Some(5.0)]);
+
+        fb.push(6.0);
+        let snap: Float32Array = fb.snapshot();
+        fb.push_opt(None);
+        assert_eq!(array2vec(&snap), vec![Some(6.0)]);
+        assert_eq!(array2vec(&fb.finish()), vec![Some(6.0), None]);
+    }
+
+    #[test]
+    fn test_num2array_counted() {
+        let data = vec![1.0f32, f32::NAN, f32::NAN, 2.0];
+        let (arr, nans): (Float32Array, usize) = num2array_counted(data.into_iter(), 4);
+        assert_eq!(nans, 2);
+        assert_eq!(arr.null_count(), nans);
+
+        let opts = vec![None, Some(f32::NAN), None, Some(1.0)];
+        let (arr, nones, nans): (Float32Array, usize, usize) =
+            opt2array_counted(opts.into_iter(), 4);
+        assert_eq!((nones, nans), (2, 1));
+        assert_eq!(arr.null_count(), 3);
+    }
+
+    #[test]
+    fn test_nan_to_null() {
+        let got: Vec<Option<f32>> = vec![1.0f32, f32::NAN]
+            .into_iter()
+            .nan_to_null::<Float32Type>()
+            .collect();
+        assert_eq!(got, vec![Some(1.0), None]);
+    }
+
+    #[test]
+    fn test_collect_float_array() {
+        let arr: Float32Array = vec![1.0f32, f32::NAN]
+            .into_iter()
+            .collect_float_array::<Float32Type>();
+        assert_eq!(arr.len(), 2);
+        assert_eq!(arr.null_count(), 1);
+
+        let arr: Float32Array = std::iter::repeat_n(0.5f32, 3).collect_float_array_with_capacity(3);
+        assert_eq!(arr.len(), 3);
+    }
+
+    #[test]
+    fn test_num2array_stats() {
+        let data = vec![2.0f32, f32::NAN, -1.0, 5.0];
+        let (arr, st): (Float32Array, FloatStats<Float32Type>) =
+            num2array_stats(data.into_iter(), 4);
+        assert_eq!(arr.len(), 4);
+        assert_eq!(st.min, Some(-1.0));
+        assert_eq!(st.max, Some(5.0));
+        assert_eq!(st.valid_count, 3);
+        assert_eq!(st.null_count, 1);
+
+        let (_, st): (Float32Array, FloatStats<Float32Type>) =
+            num2array_stats(vec![f32::NAN].into_iter(), 1);
+        assert_eq!(st.min, None);
+        assert_eq!(st.max, None);
+    }
+
+    #[test]
+    fn test_filter2array() {
+        let data = vec![1.0f32, -2.0, f32::NAN, 0.0];
+        let arr: Float32Array = filter2array(data.into_iter(), 4, |v| v >= 0.0);
+        assert_eq!(array2vec(&arr), vec![Some(1.0), None, None, Some(0.0)]);
+    }
+
+    #[test]
+    fn test_clamp2array() {
+        let data = vec![-1.0f32, 0.5, 2.0, f32::NAN];
+        let arr: Float32Array =
+            clamp2array(data.clone().into_iter(), 4, 0.0, 1.0, ClampMode::ToNull);
+        assert_eq!(array2vec(&arr), vec![None, Some(0.5), None, None]);
+
+        let arr: Float32Array = clamp2array(data.into_iter(), 4, 0.0, 1.0, ClampMode::Saturate);
+        assert_eq!(array2vec(&arr), vec![Some(0.0), Some(0.5), Some(1.0), None]);
+    }
+
+    #[test]
+    fn test_f64_iter2arr32f() {
+        let data = vec![1.5f64, 1e300, f64::NAN, f64::INFINITY, -1e300];
+        let arr: Float32Array = f64_iter2arr32f(data.into_iter());
+        assert_eq!(
+            array2vec(&arr),
+            vec![Some(1.5), None, None, Some(f32::INFINITY), None]
+        );
+    }
+
+    #[test]
+    fn test_f32_iter2arr16f() {
+        let data = vec![1.5f32, 70000.0, f32::NAN, -70000.0, 65504.0];
+        let arr: Float16Array = f32_iter2arr16f(data.into_iter());
+        assert_eq!(arr.len(), 5);
+        assert_eq!(arr.null_count(), 3);
+        assert_eq!(arr.value(0), half::f16::from_f32(1.5));
+        assert_eq!(arr.value(4), half::f16::MAX);
+    }
+
+    #[test]
+    fn test_try_result2arr64f() {
+        let ok: Vec<Result<f64, &str>> = vec![Ok(1.0), Ok(f64::NAN)];
+        let arr: Float64Array = try_result2arr64f(ok.into_iter()).unwrap();
+        assert_eq!(array2vec(&arr), vec![Some(1.0), None]);
+
+        let bad: Vec<Result<f64, &str>> = vec![Ok(1.0), Err("e1"), Err("e2")];
+        assert_eq!(try_result2arr64f(bad.into_iter()), Err("e1"));
+    }
+
+    #[test]
+    fn test_concat_arrays() {
+        let a: Float64Array = num2arr64f(vec![1.0, f64::NAN].into_iter());
+        let b: Float64Array = num2arr64f(vec![3.0].into_iter());
+        let c: Float64Array = concat_arrays(&[a, b]).unwrap();
+        assert_eq!(array2vec(&c), vec![Some(1.0), None, Some(3.0)]);
+
+        let empty: Float64Array = concat_arrays::<Float64Type>(&[]).unwrap();
+        assert_eq!(empty.len(), 0);
+    }
+
+    #[test]
+    fn test_num2array_canonical() {
+        let tiny: f64 = -f64::from_bits(1); // negative subnormal
+        let data = vec![-0.0f64, 0.0, tiny, f64::NAN];
+        let arr: Float64Array = num2array_canonical(data.into_iter(), 4);
+        assert_eq!(arr.value(0).to_bits(), 0.0f64.to_bits());
+        assert_eq!(arr.value(1).to_bits(), 0.0f64.to_bits());
+        assert_eq!(arr.value(2).to_bits(), tiny.to_bits());
+        assert!(arr.is_null(3));
+
+        let h: half::f16 = canonical_zero::<arrow::datatypes::Float16Type>(half::f16::NEG_ZERO);
+        assert_eq!(h.to_bits(), 0);
+    }
+
+    #[test]
+    fn test_str2arr64f() {
+        let data = vec!["1.5", "", "abc", "NaN", "-2"];
+        let arr: Float64Array = str2arr64f(data.clone().into_iter());
+        assert_eq!(
+            array2vec(&arr),
+            vec![Some(1.5), None, None, None, Some(-2.0)]
+        );
+
+        let e: StrParseError = try_str2arr64f(data.into_iter()).unwrap_err();
+        assert_eq!(e.index, 1);
+        assert_eq!(e.value, "");
+
+        let arr: Float64Array = try_str2arr64f(vec!["1", "NaN"].into_iter()).unwrap();
+        assert_eq!(array2vec(&arr), vec![Some(1.0), None]);
+    }
+
+    #[test]
+    fn test_round2array() {
+        let data = vec![1.23456f64, -2.5555, f64::NAN, f64::INFINITY];
+        let arr: Float64Array = round2array(data.into_iter(), 4, 3);
+        assert_eq!(
+            array2vec(&arr),
+            vec![Some(1.235), Some(-2.556), None, Some(f64::INFINITY)]
+        );
+
+        let arr: Float32Array = round2array(vec![0.5f32, 1.49].into_iter(), 2, 0);
+        assert_eq!(array2vec(&arr), vec![Some(1.0), Some(1.0)]);
+
+        let arr: Float32Array = round2array(vec![1e30f32].into_iter(), 1, 10);
+        assert_eq!(array2vec(&arr), vec![Some(1e30)]);
+
+        let arr: Float64Array = round2array(vec![1e300, 0.0].into_iter(), 2, 10);
+        assert_eq!(array2vec(&arr), vec![Some(1e300), Some(0.0)]);
+        let arr: Float64Array = round2array(vec![1e300, 1.5].into_iter(), 2, 400);
+        assert_eq!(array2vec(&arr), vec![Some(1e300), Some(1.5)]);
+
+        let h = |v: f32| half::f16::from_f32(v);
+        let data = vec![h(100.0), h(60000.0), h(1.2344), half::f16::INFINITY];
+        let arr: Float16Array = round2array(data.into_iter(), 4, 3);
+        assert_eq!(
+            array2vec(&arr),
+            vec![
+                Some(h(100.0)),
+                Some(h(60000.0)),
+                Some(h(1.234)),
+                Some(half::f16::INFINITY)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bytes2arr32f() {
+        let mut le: Vec<u8> = Vec::new();
+        let mut be: Vec<u8> = Vec::new();
+        for v in [1.5f32, f32::NAN, -3.0] {
+            le.extend_from_slice(&v.to_le_bytes());
+            be.extend_from_slice(&v.to_be_bytes());
+        }
+        let arr: Float32Array = le_bytes2arr32f(&le).unwrap();
+        assert_eq!(array2vec(&arr), vec![Some(1.5), None, Some(-3.0)]);
+        let arr: Float32Array = be_bytes2arr32f(&be).unwrap();
+        assert_eq!(array2vec(&arr), vec![Some(1.5), None, Some(-3.0)]);
+
+        assert_eq!(
+            le_bytes2arr32f(&le[..5]),
+            Err(LenError { len: 5, width: 4 })
+        );
+        assert_eq!(le_bytes2arr32f(&[]).unwrap().len(), 0);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_slice2arr64f() {
+        let data: Vec<f64> = (0..200_000)
+            .map(|i| if i % 7 == 0 { f64::NAN } else { i as f64 })
+            .collect();
+        let arr: Float64Array = par_slice2arr64f(&data);
+        let expected: Float64Array = num2arr64f(data.iter().copied());
+        assert_eq!(arr, expected);
+
+        let clean: Float64Array = par_slice2arr64f(&[1.0, 2.0]);
+        assert!(clean.nulls().is_none());
+        assert_eq!(par_slice2arr64f(&[]).len(), 0);
+    }
+
+    #[test]
+    fn test_converter() {
+        let cv: Converter<Float32Type> = Converter::default()
+            .with_capacity(8)
+            .with_nan_policy(NanPolicy::Replace(0.0))
+            .with_inf_policy(InfPolicy::ToNull);
+        let data = vec![1.0f32, f32::NAN, f32::INFINITY];
+        let arr: Float32Array = cv.build(data.into_iter());
+        assert_eq!(array2vec(&arr), vec![Some(1.0), Some(0.0), None]);
+
+        let opts = vec![None, Some(f32::NEG_INFINITY), Some(2.0)];
+        let arr: Float32Array = cv.build_opt(opts.into_iter());
+        assert_eq!(array2vec(&arr), vec![None, None, Some(2.0)]);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_val2opt_as() {
+        assert_eq!(
+            val2opt_as::<Float32Type>(&serde_json::json!(1.5)),
+            Some(1.5f32)
+        );
+        assert_eq!(val2opt_as::<Float32Type>(&serde_json::json!(1e40)), None);
+        assert_eq!(val2opt_as::<Float32Type>(&serde_json::json!("1")), None);
+        assert_eq!(
+            val2opt_as::<arrow::datatypes::Float16Type>(&serde_json::json!(70000)),
+            None
+        );
+        assert_eq!(val2opt(&serde_json::json!(1e40)), Some(1e40));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_val2opt_lenient() {
+        assert_eq!(val2opt_lenient(&serde_json::json!("2.5")), Some(2.5));
+        assert_eq!(val2opt_lenient(&serde_json::json!(2)), Some(2.0));
+        assert_eq!(val2opt_lenient(&serde_json::json!("abc")), None);
+        assert_eq!(val2opt_lenient(&serde_json::json!("NaN")), None);
+        assert_eq!(val2opt_lenient(&serde_json::json!("Infinity")), None);
+        assert_eq!(val2opt_lenient(&serde_json::json!("-inf")), None);
+        assert_eq!(val2opt(&serde_json::json!("2.5")), None);
+    }
+
+    #[test]
+    fn test_nested2list64f() {
+        let rows = vec![vec![1.0, f64::NAN], vec![], vec![3.0]];
+        let la: ListArray = nested2list64f(rows.into_iter().map(|r| r.into_iter()));
+        assert_eq!(la.len(), 3);
+        assert_eq!(la.null_count(), 0);
+        assert_eq!(la.value_offsets(), &[0, 2, 2, 3]);
+        let inner: &Float64Array = la.values().as_primitive();
+        assert_eq!(array2vec(inner), vec![Some(1.0), None, Some(3.0)]);
+    }
+
+    #[test]
+    fn test_num2dict64f() {
+        let data = vec![2.0, 1.0, 2.0, f64::NAN, 1.0, 3.0];
+        let da: DictionaryArray<Int32Type> = num2dict64f(data.into_iter());
+        assert_eq!(da.len(), 6);
+        assert_eq!(da.null_count(), 1);
+        let keys: Vec<Option<i32>> = da.keys().iter().collect();
+        assert_eq!(
+            keys,
+            vec![Some(0), Some(1), Some(0), None, Some(1), Some(2)]
+        );
+        let dict: &Float64Array = da.values().as_primitive();
+        assert_eq!(dict.values(), &[2.0, 1.0, 3.0]);
+    }
+
+    #[test]
+    fn test_rows2columns() {
+        let rows = vec![[1.0, 2.0], [f64::NAN, 4.0], [5.0, 6.0]];
+        let [xs, ys]: [Float64Array; 2] = rows2columns(rows.into_iter());
+        assert_eq!(array2vec(&xs), vec![Some(1.0), None, Some(5.0)]);
+        assert_eq!(array2vec(&ys), vec![Some(2.0), Some(4.0), Some(6.0)]);
+
+        let cols: [Float64Array; 3] = rows2columns(std::iter::empty());
+        assert!(cols.iter().all(|c| c.is_empty()));
+    }
+
+    #[test]
+    fn test_opt2array_tagged() {
+        let opts = vec![None, Some(f64::NAN), Some(1.0), None];
+        let (arr, tags): (Float64Array, NullTags) = opt2array_tagged(opts.into_iter(), 4);
+        assert_eq!(arr.null_count(), 3);
+        assert_eq!(
+            tags,
+            NullTags {
+                explicit_none_count: 2,
+                nan_count: 1
+            }
+        );
+    }
+
+    #[cfg(feature = "ipc")]
+    #[test]
+    fn test_num2ipc64f() {
+        let bytes: Vec<u8> = num2ipc64f(vec![1.0, f64::NAN].into_iter(), "v").unwrap();
+        let rdr = arrow::ipc::reader::StreamReader::try_new(bytes.as_slice(), None).unwrap();
+        assert!(rdr.schema().field(0).is_nullable());
+        let batches: Vec<RecordBatch> = rdr.map(|b| b.unwrap()).collect();
+        assert_eq!(batches.len(), 1);
+        let col: &Float64Array = batches[0].column(0).as_primitive();
+        assert_eq!(array2vec(col), vec![Some(1.0), None]);
+    }
+
+    #[test]
+    fn test_map2array() {
+        let data = vec![0.0f64, -1.0, f64::NAN];
+        let arr: Float64Array = map2array(data.into_iter(), 3, |c| c + 273.15);
+        assert_eq!(array2vec(&arr), vec![Some(273.15), Some(272.15), None]);
+
+        let arr: Float64Array = map2array(vec![-1.0f64, 4.0].into_iter(), 2, f64::sqrt);
+        assert_eq!(array2vec(&arr), vec![None, Some(2.0)]);
+    }
+
+    #[test]
+    fn test_num2arr16f_native() {
+        let data = vec![crate::half::f16::ONE, crate::half::f16::NAN];
+        let arr: Float16Array = num2arr16f_native(data.into_iter());
+        assert_eq!(array2vec(&arr), vec![Some(crate::half::f16::ONE), None]);
+    }
+
+    #[test]
+    fn test_num2array_snan_checked() {
+        let snan: f32 = f32::from_bits(0x7f80_0001);
+        assert!(Float32Type::is_signaling_nan(snan));
+        assert!(!Float32Type::is_signaling_nan(f32::NAN));
+        assert!(!Float32Type::is_signaling_nan(1.0));
+        assert!(Float64Type::is_signaling_nan(f64::from_bits(
+            0x7ff0_0000_0000_0001
+        )));
+        assert!(!Float64Type::is_signaling_nan(f64::NAN));
+        assert!(arrow::datatypes::Float16Type::is_signaling_nan(
+            half::f16::from_bits(0x7c01)
+        ));
+        assert!(!arrow::datatypes::Float16Type::is_signaling_nan(
+            half::f16::NAN
+        ));
+
+        let arr: Float32Array =
+            num2array_snan_checked(vec![1.0f32, f32::NAN].into_iter(), 2).unwrap();
+        assert_eq!(array2vec(&arr), vec![Some(1.0), None]);
+
+        let e = num2array_snan_checked::<_, Float32Type>(vec![1.0f32, snan].into_iter(), 2);
+        assert_eq!(e, Err(SignalingNanError { index: 1 }));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_arr64f2json() {
+        let mut bldr: Float64Builder = PrimitiveBuilder::new();
+        bldr.append_value(1.5);
+        bldr.append_null();
+        bldr.append_value(f64::NAN);
+        bldr.append_value(f64::INFINITY);
+        let arr: Float64Array = bldr.finish();
+        assert_eq!(
+            arr64f2json(&arr),
+            serde_json::json!([1.5, null, null, null])
+        );
+
+        let back: Float64Array = json_array2arr64f(&arr64f2json(&arr)).unwrap();
+        assert_eq!(array2vec(&back), vec![Some(1.5), None, None, None]);
+    }
+
+    #[test]
+    fn test_num2array_with_validity() {
+        let data = vec![1.0f64, 2.0, f64::NAN, 4.0];
+        let valid = vec![true, false, true];
+        let arr: Float64Array = num2array_with_validity(data.into_iter(), valid.into_iter(), 4);
+        assert_eq!(array2vec(&arr), vec![Some(1.0), None, None]);
+    }
+
+    #[test]
+    fn test_pairs2arr64f() {
+        let pairs = vec![(1.0, true), (2.0, false), (f64::NAN, true)];
+        let arr: Float64Array = pairs2arr64f(pairs.into_iter());
+        assert_eq!(array2vec(&arr), vec![Some(1.0), None, None]);
+    }
+
+    #[test]
+    fn test_slice2array_opt_nulls() {
+        let (arr, seen): (Float64Array, bool) = slice2array_opt_nulls(&[1.0, 2.0]);
+        assert!(!seen);
+        assert!(arr.nulls().is_none());
+
+        let (arr, seen): (Float64Array, bool) = slice2array_opt_nulls(&[1.0, f64::NAN]);
+        assert!(seen);
+        assert_eq!(arr.null_count(), 1);
+
+        let data = [1.0, 2.0, f64::NAN, 4.0, f64::NAN];
+        let (arr, _) = slice2array_opt_nulls::<Float64Type>(&data);
+        assert_eq!(
+            array2vec(&arr),
+            vec![Some(1.0), Some(2.0), None, Some(4.0), None]
+        );
+    }
+
+    #[test]
+    fn test_interleave2() {
+        let a: Float64Array = num2arr64f(vec![1.0, f64::NAN, 5.0, 7.0].into_iter());
+        let b: Float64Array = num2arr64f(vec![2.0, 4.0].into_iter());
+        let c: Float64Array = interleave2(&a, &b);
+        assert_eq!(
+            array2vec(&c),
+            vec![Some(1.0), Some(2.0), None, Some(4.0), Some(5.0), Some(7.0)]
+        );
+        assert_eq!(interleave2(&b, &a).len(), 6);
+    }
+
+    #[test]
+    fn test_f64_iter2dec128() {
+        let data = vec![0.1f64, -1.005, f64::NAN, 1e40, f64::INFINITY];
+        let arr: Decimal128Array = f64_iter2dec128(data.into_iter(), 2);
+        assert_eq!(
+            arr.data_type(),
+            &arrow::datatypes::DataType::Decimal128(38, 2)
+        );
+        assert_eq!(arr.value(0), 10);
+        assert_eq!(arr.value(1), -100); // -1.005 is -1.00499... in binary
+        assert!(arr.is_null(2));
+        assert!(arr.is_null(3));
+        assert!(arr.is_null(4));
+    }
+
+    #[test]
+    fn test_num2scalar64f() {
+        let sc: Scalar<Float64Array> = num2scalar64f(2.5);
+        let (arr, is_scalar) = arrow::array::Datum::get(&sc);
+        assert!(is_scalar);
+        assert_eq!(arr.len(), 1);
+        assert_eq!(arr.as_primitive::<Float64Type>().value(0), 2.5);
+
+        let sc: Scalar<Float64Array> = num2scalar64f(f64::NAN);
+        assert!(sc.into_inner().is_null(0));
+    }
+
+    #[test]
+    fn test_read_le_f64_array() {
+        let mut bytes: Vec<u8> = Vec::new();
+        for v in [1.5f64, f64::NAN, -2.0] {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        let arr: Float64Array = read_le_f64_array(bytes.as_slice(), 3).unwrap();
+        assert_eq!(array2vec(&arr), vec![Some(1.5), None, Some(-2.0)]);
+
+        let e = read_le_f64_array(&bytes[..20], 3).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert_eq!(read_le_f64_array(&[][..], 0).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_num2array_runs() {
+        let data = vec![1.0, 1.0, 2.0, f64::NAN, 2.0, 2.0, 1.0];
+        let (arr, runs): (Float64Array, Vec<(f64, usize)>) = num2array_runs(data.into_iter());
+        assert_eq!(arr.len(), 7);
+        assert_eq!(arr.null_count(), 1);
+        assert_eq!(runs, vec![(1.0, 2), (2.0, 1), (2.0, 2), (1.0, 1)]);
+
+        let (_, runs): (Float64Array, Vec<(f64, usize)>) = num2array_runs(std::iter::empty());
+        assert!(runs.is_empty());
+    }
+
+    #[test]
+    fn test_extend_builder() {
+        let mut bldr: Float64Builder = PrimitiveBuilder::new();
+        bldr.append_value(0.0);
+        extend_builder(&mut bldr, vec![1.0, f64::NAN].into_iter());
+        bldr.append_null();
+        extend_builder_opt(&mut bldr, vec![None, Some(2.0)].into_iter());
+        let arr: Float64Array = bldr.finish();
+        assert_eq!(
+            array2vec(&arr),
+            vec![Some(0.0), Some(1.0), None, None, None, Some(2.0)]
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_stream2arr64f() {
+        use futures::StreamExt;
+
+        let st = futures::stream::iter(vec![1.0, f64::NAN, 3.0]);
+        let arr: Float64Array = futures::executor::block_on(stream2arr64f(st, 3));
+        assert_eq!(array2vec(&arr), vec![Some(1.0), None, Some(3.0)]);
+
+        let st = futures::stream::iter(vec![1.0, 2.0, 3.0]);
+        let chunks: Vec<Float64Array> = futures::executor::block_on(stream2chunks(st, 2).collect());
+        let lens: Vec<usize> = chunks.iter().map(|a| a.len()).collect();
+        assert_eq!(lens, vec![2, 1]);
+    }
+
+    #[test]
+    fn test_sorted_num2arr64f() {
+        let data = vec![3.0, f64::NAN, -1.0, 2.0];
+        let arr: Float64Array = sorted_num2arr64f(data.clone().into_iter(), true);
+        assert_eq!(
+            array2vec(&arr),
+            vec![Some(-1.0), Some(2.0), Some(3.0), None]
+        );
+
+        let arr: Float64Array = sorted_num2arr64f(data.clone().into_iter(), false);
+        assert_eq!(
+            array2vec(&arr),
+            vec![Some(3.0), Some(2.0), Some(-1.0), None]
+        );
+
+        let arr: Float64Array = total_cmp2array(data.into_iter(), true, true);
+        assert_eq!(arr.null_count(), 0);
+        assert!(arr.value(3).is_nan());
+
+        let arr: Float64Array = sorted_num2arr64f(vec![f64::NAN; 2].into_iter(), true);
+        assert_eq!(arr.null_count(), 2);
+    }
+
+    #[test]
+    fn test_opt2array_of_and_all_null() {
+        fn nulls_only<T: FloatType>(n: usize) -> PrimitiveArray<T> {
+            opt2array_of::<T>(std::iter::repeat_n(None, n))
+        }
+
+        let arr: Float32Array = nulls_only(3);
+        assert!(array_is_all_null(&arr));
+        assert!(array_is_all_null(&nulls_only::<Float64Type>(0)));
+
+        let arr: Float32Array = num2array_of::<Float32Type>(vec![f32::NAN, 1.0].into_iter());
+        assert!(!array_is_all_null(&arr));
+        assert!(!array_is_all_null(&num2arr32f(std::iter::once(1.0))));
+    }
+
+    #[test]
+    fn test_quantize2array() {
+        let data = vec![0.1f64, 0.13, 0.375, 0.625, f64::NAN];
+        let arr: Float64Array = quantize2array(data.into_iter(), 5, 0.25).unwrap();
+        assert_eq!(
+            array2vec(&arr),
+            vec![Some(0.0), Some(0.25), Some(0.5), Some(0.5), None]
+        );
+
+        let h = |v: f32| half::f16::from_f32(v);
+        let arr: Float16Array =
+            quantize2array(vec![h(100.0), h(1.0)].into_iter(), 2, h(0.001)).unwrap();
+        assert_eq!(array2vec(&arr), vec![Some(h(100.0)), Some(h(1.0))]);
+
+        let arr: Float32Array = quantize2array(vec![3.0e38f32, 0.75].into_iter(), 2, 0.5).unwrap();
+        assert_eq!(array2vec(&arr), vec![Some(3.0e38), Some(1.0)]);
+
+        let arr: Float64Array = quantize2array(vec![1e308].into_iter(), 1, 0.1).unwrap();
+        assert_eq!(array2vec(&arr), vec![Some(1e308)]);
+
+        let e = quantize2array::<_, Float64Type>(std::iter::empty(), 0, 0.0);
+        assert_eq!(e, Err(InvalidParamError { name: "step" }));
+        let e = quantize2array::<_, Float64Type>(std::iter::empty(), 0, f64::NAN);
+        assert!(e.is_err());
+    }
+
+    #[test]
+    fn test_bits2arr64f() {
+        let data = [1.5f64, -0.0, f64::MAX, f64::NAN];
+        let arr: Float64Array = bits2arr64f(data.iter().map(|v| v.to_bits()));
+        assert!(arr.is_null(3));
+        for (i, v) in data[..3].iter().enumerate() {
+            assert_eq!(arr.value(i).to_bits(), v.to_bits());
+        }
+
+        let arr: Float32Array = bits2arr32f([2.5f32.to_bits(), 0x7fc0_0000].into_iter());
+        assert_eq!(array2vec(&arr), vec![Some(2.5), None]);
+    }
+
+    #[test]
+    fn test_timeseries2batch() {
+        let batch: RecordBatch = timeseries2batch(&[10, 20], &[1.0, f64::NAN], "v").unwrap();
+        let schema = batch.schema();
+        assert!(!schema.field(0).is_nullable());
+        assert_eq!(
+            schema.field(0).data_type(),
+            &arrow::datatypes::DataType::Timestamp(arrow::datatypes::TimeUnit::Nanosecond, None)
+        );
+        assert_eq!(schema.field(1).name(), "v");
+        assert!(schema.field(1).is_nullable());
+        assert_eq!(batch.column(1).null_count(), 1);
+
+        assert!(timeseries2batch(&[10], &[1.0, 2.0], "v").is_err());
+    }
+
+    #[test]
+    fn test_as_float_array() {
+        let arr: ArrayRef = Arc::new(num2arr32f(vec![1.0f32, f32::NAN].into_iter()));
+        let f: &Float32Array = as_float_array::<Float32Type>(arr.as_ref()).unwrap();
+        assert_eq!(array2vec(f), vec![Some(1.0), None]);
+        assert!(as_float_array::<Float64Type>(arr.as_ref()).is_none());
+
+        let ints: ArrayRef = Arc::new(int2arr32i(std::iter::once(1)));
+        assert!(as_float_array::<Float32Type>(ints.as_ref()).is_none());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_val2opt_bool_numeric() {
+        assert_eq!(val2opt_bool_numeric(&serde_json::json!(true)), Some(1.0));
+        assert_eq!(val2opt_bool_numeric(&serde_json::json!(false)), Some(0.0));
+        assert_eq!(val2opt_bool_numeric(&serde_json::json!(2.5)), Some(2.5));
+        assert_eq!(val2opt_bool_numeric(&serde_json::json!("1")), None);
+        assert_eq!(val2opt(&serde_json::json!(true)), None);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_flatten2arr64f() {
+        let v = serde_json::json!([[1, 2], [3, [4, "x"]], []]);
+        let arr: Float64Array = json_flatten2arr64f(&v);
+        assert_eq!(
+            array2vec(&arr),
+            vec![Some(1.0), Some(2.0), Some(3.0), Some(4.0), None]
+        );
+
+        let arr: Float64Array = json_flatten2arr64f(&serde_json::json!(5));
+        assert_eq!(array2vec(&arr), vec![Some(5.0)]);
+
+        let arr: Float64Array = json_flatten2arr64f_with_depth(&v, 1);
+        assert_eq!(array2vec(&arr), vec![None, None, None]);
+    }
+
+    #[test]
+    fn test_num2chunks_by_bytes() {
+        // 8 f64 values take 64 bytes + 1 validity byte.
+        assert_eq!(elements_within_bytes::<Float64Type>(65), 8);
+        assert_eq!(elements_within_bytes::<Float64Type>(64), 7);
+        assert_eq!(elements_within_bytes::<Float64Type>(0), 0);
+
+        let data = vec![1.0f64; 20];
+        let chunks: Vec<Float64Array> = num2chunks_by_bytes(data.into_iter(), 65).collect();
+        let lens: Vec<usize> = chunks.iter().map(|a| a.len()).collect();
+        assert_eq!(lens, vec![8, 8, 4]);
+
+        let tiny: Vec<Float64Array> = num2chunks_by_bytes(vec![1.0f64; 2].into_iter(), 1).collect();
+        assert_eq!(tiny.len(), 2);
+
+        let n: usize = elements_within_bytes::<Float64Type>(usize::MAX);
+        let bytes = |n: u128| n * 8 + n.div_ceil(8);
+        assert!(bytes(n as u128) <= usize::MAX as u128);
+        assert!(bytes(n as u128 + 1) > usize::MAX as u128);
+
+        let all: Vec<Float64Array> =
+            num2chunks_by_bytes(vec![1.0f64; 3].into_iter(), usize::MAX).collect();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].len(), 3);
+    }
+
+    #[test]
+    fn test_all_null_array() {
+        let arr: Float16Array = all_null_array(4);
+        assert_eq!(arr.len(), 4);
+        assert_eq!(arr.null_count(), 4);
+        assert!(array_is_all_null(&arr));
+        assert!(array2vec_nan(&arr).iter().all(|v| v.is_nan()));
+        assert!(Float64Type::nan().is_nan());
+    }
+
+    #[test]
+    fn test_first_nan_index() {
+        assert_eq!(
+            first_nan_index(vec![1.0, f64::NAN, f64::NAN].into_iter()),
+            Some(1)
+        );
+        assert_eq!(first_nan_index(vec![1.0, 2.0].into_iter()), None);
+
+        let mut it = vec![f32::NAN, 1.0, 2.0].into_iter();
+        assert_eq!(first_nan_index_of::<_, Float32Type>(&mut it), Some(0));
+        assert_eq!(it.len(), 2); // short-circuits
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_float_column_serde() {
+        let col: FloatColumn<Float32Type> =
+            FloatColumn(num2arr32f(vec![1.5f32, f32::NAN, 2.0].into_iter()));
+        let json: String = serde_json::to_string(&col).unwrap();
+        assert_eq!(json, "[1.5,null,2.0]");
+
+        let back: FloatColumn<Float32Type> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, col);
+
+        let col: FloatColumn<Float32Type> = FloatColumn(num2arr32f(vec![0.1f32].into_iter()));
+        assert_eq!(serde_json::to_string(&col).unwrap(), "[0.1]");
+
+        let col: FloatColumn<arrow::datatypes::Float16Type> =
+            FloatColumn(num2arr16f(vec![half::f16::from_f32(0.5)].into_iter()));
+        assert_eq!(serde_json::to_string(&col).unwrap(), "[0.5]");
+
+        let e: Result<FloatColumn<Float32Type>, _> = serde_json::from_str("1.0");
+        assert!(e.is_err());
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn test_ndarray2arr64f() {
+        let a = ndarray::Array1::from(vec![1.0, f64::NAN, 3.0, 4.0]);
+        let arr: Float64Array = ndarray2arr64f(&a);
+        assert_eq!(array2vec(&arr), vec![Some(1.0), None, Some(3.0), Some(4.0)]);
+
+        let strided = a.slice(ndarray::s![..;2]);
+        let arr: Float64Array = ndarray2arr64f(&strided);
+        assert_eq!(array2vec(&arr), vec![Some(1.0), Some(3.0)]);
+
+        let back: ndarray::Array1<f64> = arr64f2ndarray(&ndarray2arr64f(&a));
+        assert_eq!(back.len(), 4);
+        assert!(back[1].is_nan());
+
+        let a32 = ndarray::Array1::from(vec![1.0f32, f32::NAN]);
+        assert_eq!(ndarray2arr32f(&a32).null_count(), 1);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_num2opt_large_integer() {
+        let big: serde_json::Value =
+            serde_json::from_str("123456789012345678901234567890").unwrap();
+        assert_eq!(val2opt(&big), Some(1.2345678901234568e29));
+
+        // Only `arbitrary_precision` accepts a literal beyond the f64 range.
+        let huge: String = format!("1{}", "0".repeat(400));
+        let parsed: Result<serde_json::Value, _> = serde_json::from_str(&huge);
+        #[cfg(feature = "arbitrary_precision")]
+        assert_eq!(val2opt(&parsed.unwrap()), None);
+        #[cfg(not(feature = "arbitrary_precision"))]
+        assert!(parsed.is_err());
+    }
+
+    #[test]
+    fn test_scrub_nan() {
+        let mut bldr: Float64Builder = PrimitiveBuilder::new();
+        bldr.append_value(1.0);
+        bldr.append_value(f64::NAN);
+        bldr.append_null();
+        bldr.append_value(4.0);
+        let arr: Float64Array = bldr.finish();
+
+        let scrubbed: Float64Array = scrub_nan(&arr);
+        assert_eq!(array2vec(&scrubbed), vec![Some(1.0), None, None, Some(4.0)]);
+        assert_eq!(scrubbed.values().as_ptr(), arr.values().as_ptr());
+
+        let clean: Float64Array = num2arr64f(vec![1.0, 2.0].into_iter());
+        assert_eq!(scrub_nan(&clean), clean);
+    }
+
+    #[test]
+    fn test_num2dict64f_keyed() {
+        let data = vec![1.0, 2.0, 1.0, f64::NAN];
+        let da: DictionaryArray<arrow::datatypes::Int16Type> =
+            num2dict64f_keyed(data.into_iter()).unwrap();
+        let keys: Vec<Option<i16>> = da.keys().iter().collect();
+        assert_eq!(keys, vec![Some(0), Some(1), Some(0), None]);
+
+        let many = (0..300).map(f64::from);
+        let e = num2dict64f_keyed::<arrow::datatypes::Int8Type, _>(many);
+        assert_eq!(e.unwrap_err(), KeyOverflowError { distinct: 128 });
+
+        let ok = (0..300).map(|i| f64::from(i % 100));
+        assert!(num2dict64f_keyed::<arrow::datatypes::Int8Type, _>(ok).is_ok());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_num2opt_f32() {
+        let n = |s: &str| -> serde_json::Number { serde_json::from_str(s).unwrap() };
+        assert_eq!(num2opt_f32(&n("1.5")), Some(1.5));
+        assert_eq!(num2opt_f32(&n("0.1")), Some(0.1f32));
+        assert_eq!(num2opt_f32(&n("1e40")), None);
+        assert_eq!(num2opt_f32(&n("-1e40")), None);
+        assert_eq!(num2opt_f32(&n("3.4e38")), Some(3.4e38));
+    }
+
+    #[test]
+    fn test_moving_avg2arr64f() {
+        let data = vec![1.0, 2.0, 3.0, f64::NAN, 5.0, 6.0, 7.0];
+        let arr: Float64Array = moving_avg2arr64f(data.into_iter(), 2).unwrap();
+        assert_eq!(
+            array2vec(&arr),
+            vec![None, Some(1.5), Some(2.5), None, None, Some(5.5), Some(6.5)]
+        );
+
+        let data = vec![f64::INFINITY, 1.0, 3.0];
+        let arr: Float64Array = moving_avg2arr64f(data.into_iter(), 2).unwrap();
+        assert_eq!(array2vec(&arr), vec![None, Some(f64::INFINITY), Some(2.0)]);
+
+        let data = vec![1e16, 1.0, 1.0, 1.0];
+        let arr: Float64Array = moving_avg2arr64f(data.into_iter(), 1).unwrap();
+        assert_eq!(
+            array2vec(&arr),
+            vec![Some(1e16), Some(1.0), Some(1.0), Some(1.0)]
+        );
+
+        let data = vec![1e308, 1e308, 1.0, 1.0, 1.0, 1.0];
+        let arr: Float64Array = moving_avg2arr64f(data.into_iter(), 2).unwrap();
+        assert_eq!(
+            array2vec(&arr),
+            vec![
+                None,
+                Some(1e308),
+                Some(5e307),
+                Some(1.0),
+                Some(1.0),
+                Some(1.0)
+            ]
+        );
+
+        let mut data: Vec<f64> = vec![1e300, -1e300];
+        data.extend((0..1000).map(|i| f64::from(i % 7) * 0.1));
+        let arr: Float64Array = moving_avg2arr64f(data.clone().into_iter(), 3).unwrap();
+        for i in 4..data.len() {
+            let want: f64 = data[i - 2..=i].iter().sum::<f64>() / 3.0;
+            assert!((arr.value(i) - want).abs() < 1e-12);
+        }
+
+        let e = moving_avg2arr64f(std::iter::empty(), 0);
+        assert_eq!(e, Err(InvalidParamError { name: "window" }));
+    }
+
+    #[test]
+    fn test_sparse2arr64f() {
+        let pairs = vec![(3, 1.0), (0, 2.0), (3, 5.0), (1, f64::NAN)];
+        let arr: Float64Array = sparse2arr64f(pairs.into_iter(), 5).unwrap();
+        assert_eq!(
+            array2vec(&arr),
+            vec![Some(2.0), None, None, Some(5.0), None]
+        );
+
+        let e = sparse2arr64f(vec![(5, 1.0)].into_iter(), 5);
+        assert_eq!(e, Err(IndexOutOfRangeError { index: 5, len: 5 }));
+    }
+
+    #[test]
+    fn test_num2array_with_quantile() {
+        let data = vec![3.0, 1.0, f64::NAN, 4.0, 2.0];
+        let (arr, med) = num2array_with_quantile(data.clone().into_iter(), 0.5).unwrap();
+        assert_eq!(arr.len(), 5);
+        assert_eq!(med, Some(2.5));
+
+        let (_, lo) = num2array_with_quantile(data.clone().into_iter(), 0.0).unwrap();
+        let (_, hi) = num2array_with_quantile(data.clone().into_iter(), 1.0).unwrap();
+        assert_eq!((lo, hi), (Some(1.0), Some(4.0)));
+
+        let (_, none) = num2array_with_quantile(vec![f64::NAN].into_iter(), 0.5).unwrap();
+        assert_eq!(none, None);
+
+        assert!(num2array_with_quantile(data.into_iter(), 1.5).is_err());
+    }
+
+    #[test]
+    fn test_columns2batch() {
+        let xs: Float64Array = num2arr64f(vec![1.0, f64::NAN].into_iter());
+        let ys: Float64Array = num2arr64f(vec![3.0, 4.0].into_iter());
+        let batch: RecordBatch = columns2batch(&[("x", xs.clone()), ("y", ys)]).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.num_columns(), 2);
+        assert!(batch.schema().fields().iter().all(|f| f.is_nullable()));
+        assert_eq!(batch.column(0).null_count(), 1);
+
+        let zs: Float64Array = num2arr64f(vec![5.0].into_iter());
+        let err = columns2batch(&[("x", xs), ("z", zs)]).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("column z has length 1 but expected 2")
+        );
+
+        assert_eq!(columns2batch(&[]).unwrap().num_columns(), 0);
+    }
+
+    #[test]
+    fn test_fits_exactly() {
+        use arrow::datatypes::{Float16Type, Float32Type, Float64Type};
+
+        assert!(fits_exactly::<Float32Type>(0.5));
+        assert!(!fits_exactly::<Float32Type>(0.1));
+        assert!(fits_exactly::<Float64Type>(0.1));
+        assert!(fits_exactly::<Float16Type>(-0.0));
+        assert!(fits_exactly::<Float16Type>(2048.0));
+        assert!(!fits_exactly::<Float16Type>(2049.0));
+        assert!(!fits_exactly::<Float16Type>(1e6));
+        assert!(fits_exactly::<Float16Type>(f64::INFINITY));
+        assert!(fits_exactly::<Float16Type>(f64::NAN));
+    }
+
+    #[test]
+    fn test_dedup_runs2arr64f() {
+        let data = vec![1.0, 1.0, f64::NAN, 1.0, 2.0, 2.0, 1.0];
+        let arr: Float64Array = dedup_runs2arr64f(data.into_iter());
+        let got: Vec<Option<f64>> = arr.iter().collect();
+        assert_eq!(
+            got,
+            vec![Some(1.0), None, None, None, Some(2.0), None, Some(1.0)]
+        );
+
+        let first_nan: Float64Array = dedup_runs2arr64f(vec![f64::NAN, 3.0].into_iter());
+        assert_eq!(first_nan.iter().collect::<Vec<_>>(), vec![None, Some(3.0)]);
+    }
+
+    #[test]
+    fn test_bytes2array() {
+        use arrow::datatypes::{Float16Type, Float64Type};
+
+        let vals = [
+            half::f16::from_f32(1.5),
+            half::f16::NAN,
+            half::f16::from_f32(-3.0),
+        ];
+        let le: Vec<u8> = vals.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let be: Vec<u8> = vals.iter().flat_map(|v| v.to_be_bytes()).collect();
+        let ne: Vec<u8> = vals.iter().flat_map(|v| v.to_ne_bytes()).collect();
+        let want = vec![Some(vals[0]), None, Some(vals[2])];
+        for (bytes, endian) in [
+            (le, Endianness::Little),
+            (be, Endianness::Big),
+            (ne, Endianness::Native),
+        ] {
+            let arr: Float16Array = bytes2array::<Float16Type>(&bytes, endian).unwrap();
+            assert_eq!(array2vec(&arr), want);
+        }
+
+        let be: Vec<u8> = 2.25f64.to_be_bytes().to_vec();
+        let arr: Float64Array = bytes2array::<Float64Type>(&be, Endianness::Big).unwrap();
+        assert_eq!(arr.value(0), 2.25);
+
+        assert_eq!(
+            bytes2array::<Float16Type>(&[0u8; 3], Endianness::Little).unwrap_err(),
+            LenError { len: 3, width: 2 }
+        );
+    }
+
+    #[test]
+    fn test_num2array_cancelable() {
+        let stop = AtomicBool::new(false);
+        let arr: Float64Array = num2array_cancelable((0..10_000).map(f64::from), 16, &stop);
+        assert_eq!(arr.len(), 10_000);
+
+        let nums = (0..10_000).map(f64::from).inspect(|&v| {
+            if v == 10.0 {
+                stop.store(true, Ordering::Relaxed);
+            }
+        });
+        let arr: Float64Array = num2array_cancelable(nums, 16, &stop);
+        assert_eq!(arr.len(), 4096);
+
+        let arr: Float64Array = num2array_cancelable(vec![1.0].into_iter(), 1, &stop);
+        assert!(arr.is_empty());
+    }
+
+    #[test]
+    fn test_finite_mask() {
+        let data = [1.0f32, f32::NAN, f32::INFINITY, -0.0, f32::NEG_INFINITY];
+        let mask: BooleanArray = finite_mask::<arrow::datatypes::Float32Type>(&data);
+        assert_eq!(mask.null_count(), 0);
+        let got: Vec<bool> = mask.values().iter().collect();
+        assert_eq!(got, vec![true, false, false, true, false]);
+    }
+
+    #[test]
+    fn test_coalesce() {
+        let primary: Float64Array = num2arr64f(vec![1.0, f64::NAN, f64::NAN].into_iter());
+        let fallback: Float64Array = num2arr64f(vec![9.0, 2.0, f64::NAN].into_iter());
+        let arr: Float64Array = coalesce(&primary, &fallback).unwrap();
+        assert_eq!(array2vec(&arr), vec![Some(1.0), Some(2.0), None]);
+
+        let short: Float64Array = num2arr64f(vec![1.0].into_iter());
+        assert!(coalesce(&primary, &short).is_err());
+    }
+
+    #[test]
+    fn test_map2batch() {
+        let mut cols: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+        cols.insert("b".into(), vec![1.0, f64::NAN]);
+        cols.insert("a".into(), vec![3.0, 4.0]);
+        let batch: RecordBatch = map2batch(&cols).unwrap();
+        assert_eq!(batch.schema().field(0).name(), "a");
+        assert_eq!(batch.schema().field(1).name(), "b");
+        assert_eq!(batch.column(1).null_count(), 1);
+
+        cols.insert("c".into(), vec![5.0]);
+        assert!(map2batch(&cols).is_err());
+
+        let empty: RecordBatch = map2batch(&BTreeMap::new()).unwrap();
+        assert_eq!((empty.num_columns(), empty.num_rows()), (0, 0));
+    }
+
+    #[test]
+    fn test_num2array_max_nan_ratio() {
+        let data = vec![1.0, f64::NAN, 3.0, 4.0];
+        let arr: Float64Array = num2array_max_nan_ratio(data.clone().into_iter(), 4, 0.25).unwrap();
+        assert_eq!(arr.null_count(), 1);
+
+        let err: NanRatioError = num2array_max_nan_ratio(data.into_iter(), 4, 0.2).unwrap_err();
+        assert_eq!(
+            err,
+            NanRatioError::TooManyNaN(TooManyNaN {
+                ratio: 0.25,
+                nan_count: 1,
+                len: 4,
+            })
+        );
+
+        assert!(num2array_max_nan_ratio(std::iter::empty(), 0, 0.0).is_ok());
+
+        for bad in [-0.5, f64::NAN] {
+            let err = num2array_max_nan_ratio(vec![f64::NAN; 3].into_iter(), 3, bad).unwrap_err();
+            assert_eq!(
+                err,
+                NanRatioError::InvalidParam(InvalidParamError { name: "max_ratio" })
+            );
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_values2arr16f() {
+        let vals = vec![
+            serde_json::json!(1.5),
+            serde_json::json!(1e6),
+            serde_json::json!("x"),
+            serde_json::json!(-65504.0),
+        ];
+        let arr: Float16Array = json_values2arr16f(vals.into_iter());
+        assert_eq!(
+            array2vec(&arr),
+            vec![
+                Some(half::f16::from_f32(1.5)),
+                None,
+                None,
+                Some(half::f16::MIN),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_num2array_with_sum() {
+        let mut data: Vec<f64> = vec![1.0];
+        data.extend(std::iter::repeat_n(1e-16, 10_000));
+        data.push(f64::NAN);
+        let (arr, sum) = num2array_with_sum(data.into_iter(), 16);
+        assert_eq!(arr.null_count(), 1);
+        assert!((sum - (1.0 + 1e-12)).abs() < 1e-15);
+
+        let (_, inf) = num2array_with_sum(vec![1.0, f64::INFINITY].into_iter(), 2);
+        assert_eq!(inf, f64::INFINITY);
+
+        let (_, empty) = num2array_with_sum(std::iter::empty(), 0);
+        assert_eq!(empty, 0.0);
+    }
+
+    #[test]
+    fn test_vectors2fixedlist32f() {
+        let rows = vec![[1.0f32, f32::NAN, 3.0], [4.0, 5.0, 6.0]];
+        let arr: FixedSizeListArray = vectors2fixedlist32f(rows.into_iter());
+        assert_eq!(arr.len(), 2);
+        assert_eq!(arr.value_length(), 3);
+        assert_eq!(arr.values().null_count(), 1);
+
+        let empty: FixedSizeListArray = vectors2fixedlist32f::<4, _>(std::iter::empty());
+        assert_eq!(empty.len(), 0);
+        assert_eq!(empty.value_length(), 4);
+    }
+
+    #[test]
+    fn test_num2array_keep_nan() {
+        let data = vec![1.0f64, f64::NAN, 3.0];
+        let (arr, mask) =
+            num2array_keep_nan::<_, arrow::datatypes::Float64Type>(data.into_iter(), 3);
+        assert_eq!(arr.null_count(), 0);
+        assert!(arr.value(1).is_nan());
+        assert_eq!(mask.null_count(), 0);
+        assert_eq!(
+            mask.values().iter().collect::<Vec<bool>>(),
+            vec![false, true, false]
+        );
+    }
+
+    #[test]
+    fn test_num2array_drop_log() {
+        let data = vec![f64::NAN, 1.0, f64::NAN, 3.0];
+        let (arr, dropped) = num2array_drop_log(data.into_iter(), 4);
+        assert_eq!(arr.null_count(), 2);
+        assert_eq!(dropped, vec![0, 2]);
+
+        let (_, clean) = num2array_drop_log(vec![1.0, 2.0].into_iter(), 2);
+        assert!(clean.is_empty());
+    }
+
+    #[test]
+    fn test_split_nulls() {
+        let arr: Float64Array = num2arr64f(vec![f64::NAN, 1.0, f64::NAN, 3.0].into_iter());
+        let (values, nulls) = split_nulls(&arr);
+        assert_eq!(values, vec![1.0, 3.0]);
+        assert_eq!(nulls, vec![0, 2]);
+
+        let empty: Float64Array = all_null_array(3);
+        let (values, nulls) = split_nulls(&empty);
+        assert!(values.is_empty());
+        assert_eq!(nulls, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_durations2arr64f() {
+        let ds = vec![Duration::from_millis(1500), Duration::ZERO];
+        let arr: Float64Array = durations2arr64f(ds.into_iter());
+        assert_eq!(array2vec(&arr), vec![Some(1.5), Some(0.0)]);
+
+        let ds = vec![Some(Duration::from_micros(250)), None];
+        let arr: Float64Array = durations2arr64f_opt(ds.into_iter());
+        assert_eq!(array2vec(&arr), vec![Some(0.00025), None]);
+    }
+
+    #[test]
+    fn test_monotone2arr64f() {
+        let data = vec![1.0, 1.0, 0.5, f64::NAN, 2.0, 1.5, 3.0];
+        let arr: Float64Array = monotone2arr64f(data.clone().into_iter(), false);
+        assert_eq!(
+            array2vec(&arr),
+            vec![Some(1.0), Some(1.0), None, None, Some(2.0), None, Some(3.0)]
+        );
+
+        let arr: Float64Array = monotone2arr64f(data.into_iter(), true);
+        assert_eq!(
+            array2vec(&arr),
+            vec![Some(1.0), None, None, None, Some(2.0), None, Some(3.0)]
+        );
+    }
+
+    #[test]
+    fn test_opt_f64_iter2arr32f() {
+        let data = vec![
+            Some(1.5),
+            None,
+            Some(f64::NAN),
+            Some(1e300),
+            Some(f64::INFINITY),
+        ];
+        let arr: Float32Array = opt_f64_iter2arr32f(data.clone().into_iter());
+        assert_eq!(
+            array2vec(&arr),
+            vec![Some(1.5), None, None, None, Some(f32::INFINITY)]
+        );
+
+        let arr: Float16Array = opt_f64_iter2arr16f(data.into_iter());
+        assert_eq!(
+            array2vec(&arr),
+            vec![
+                Some(half::f16::from_f32(1.5)),
+                None,
+                None,
+                None,
+                Some(half::f16::INFINITY),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_num2dict64f_tol() {
+        let data = vec![
+            1.0,
+            1.05,
+            2.0,
+            f64::NAN,
+            0.96,
+            1.2,
+            2.05,
+            f64::INFINITY,
+            f64::INFINITY,
+        ];
+        let dict: DictionaryArray<Int32Type> = num2dict64f_tol(data.into_iter(), 0.1).unwrap();
+        let keys: Vec<Option<i32>> = dict.keys().iter().collect();
+        assert_eq!(
+            keys,
+            vec![
+                Some(0),
+                Some(0),
+                Some(1),
+                None,
+                Some(0),
+                Some(2),
+                Some(1),
+                Some(3),
+                Some(3)
+            ]
+        );
+        let values: &Float64Array = dict.values().as_primitive();
+        assert_eq!(values.values().to_vec(), vec![1.0, 2.0, 1.2, f64::INFINITY]);
+
+        let exact: DictionaryArray<Int32Type> =
+            num2dict64f_tol(vec![1.0, 1.0].into_iter(), 0.0).unwrap();
+        assert_eq!(exact.keys().values().to_vec(), vec![0, 0]);
+
+        for bad in [-1.0, f64::NAN] {
+            let e = num2dict64f_tol(vec![1.0].into_iter(), bad).unwrap_err();
+            assert_eq!(e, InvalidParamError { name: "tol" });
+        }
+    }
+
+    #[test]
+    fn test_try_finite2arr64f() {
+        let arr: Float64Array = try_finite2arr64f(vec![1.0, -0.0].into_iter()).unwrap();
+        assert_eq!(arr.len(), 2);
+        assert_eq!(arr.null_count(), 0);
+
+        let err = try_finite2arr64f(vec![1.0, f64::NAN].into_iter()).unwrap_err();
+        assert_eq!(err.index, 1);
+        assert!(err.value.is_nan());
+
+        for bad in [f64::INFINITY, f64::NEG_INFINITY] {
+            let err = try_finite2arr64f(vec![bad, 1.0].into_iter()).unwrap_err();
+            assert_eq!(
+                err,
+                NonFiniteError {
+                    index: 0,
+                    value: bad
+                }
+            );
         }
     }
+
+    #[test]
+    fn test_vec2array() {
+        use arrow::datatypes::Float64Type;
+
+        let data: Vec<f64> = vec![1.0, 2.0];
+        let ptr: *const f64 = data.as_ptr();
+        let arr: Float64Array = vec2array_unchecked::<Float64Type>(data);
+        assert_eq!(arr.values().as_ptr(), ptr);
+        assert!(arr.nulls().is_none());
+
+        let raw: Float64Array = vec2array_unchecked::<Float64Type>(vec![f64::NAN]);
+        assert_eq!(raw.null_count(), 0);
+        assert!(raw.value(0).is_nan());
+
+        let arr: Float64Array = vec2array::<Float64Type>(vec![1.0, f64::NAN]);
+        assert_eq!(array2vec(&arr), vec![Some(1.0), None]);
+        assert!(vec2array::<Float64Type>(vec![1.0]).nulls().is_none());
+    }
+
+    #[test]
+    fn test_denoise2arr64f() {
+        let data = vec![1e-9, -1e-9, 0.001, -0.001, 0.5, f64::NAN, 0.0];
+        let arr: Float64Array = denoise2arr64f(data.clone().into_iter(), 0.001).unwrap();
+        assert_eq!(
+            array2vec(&arr),
+            vec![None, None, Some(0.001), Some(-0.001), Some(0.5), None, None]
+        );
+
+        let arr: Float64Array = denoise2arr64f(data.into_iter(), 0.0).unwrap();
+        assert_eq!(arr.null_count(), 1);
+
+        assert_eq!(
+            denoise2arr64f(std::iter::empty(), -1.0).unwrap_err(),
+            InvalidParamError { name: "epsilon" }
+        );
+        assert!(denoise2arr64f(std::iter::empty(), f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_days_f64_2timestamp_ms() {
+        let data = vec![1.0, -0.5, 1.5e-8, f64::NAN, f64::INFINITY, 1e14, -1e14];
+        let arr: TimestampMillisecondArray = days_f64_2timestamp_ms(data.into_iter());
+        let got: Vec<Option<i64>> = arr.iter().collect();
+        assert_eq!(
+            got,
+            vec![
+                Some(86_400_000),
+                Some(-43_200_000),
+                Some(1),
+                None,
+                None,
+                None,
+                None
+            ]
+        );
+
+        let edge: f64 = i64::MIN as f64 / MILLIS_PER_DAY;
+        let arr: TimestampMillisecondArray = days_f64_2timestamp_ms(vec![edge].into_iter());
+        assert_eq!(arr.value(0), i64::MIN);
+    }
+
+    #[test]
+    fn test_cast_float() {
+        use arrow::datatypes::{Float16Type, Float32Type, Float64Type};
+
+        let arr: Float64Array = Float64Array::from(vec![
+            Some(1.5),
+            None,
+            Some(f64::NAN),
+            Some(1e10),
+            Some(f64::NEG_INFINITY),
+        ]);
+        let narrow: Float16Array = cast_float::<Float64Type, Float16Type>(&arr);
+        assert_eq!(
+            array2vec(&narrow),
+            vec![
+                Some(half::f16::from_f32(1.5)),
+                None,
+                None,
+                None,
+                Some(half::f16::NEG_INFINITY),
+            ]
+        );
+
+        let wide: Float64Array =
+            cast_float::<Float32Type, Float64Type>(&Float32Array::from(vec![Some(0.25f32), None]));
+        assert_eq!(array2vec(&wide), vec![Some(0.25), None]);
+    }
+
+    #[test]
+    fn test_opt_slice2arr64f() {
+        let arr: Float64Array = opt_slice2arr64f(&[Some(1.0), None, Some(f64::NAN), Some(4.0)]);
+        assert_eq!(array2vec(&arr), vec![Some(1.0), None, None, Some(4.0)]);
+
+        let clean: Float64Array = opt_slice2arr64f(&[Some(1.0)]);
+        assert!(clean.nulls().is_none());
+
+        assert!(opt_slice2arr64f(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_num2array_ftz() {
+        let tiny: f32 = f32::MIN_POSITIVE / 2.0;
+        let data = vec![tiny, -tiny, f32::MIN_POSITIVE, -0.0, 1.0, f32::NAN];
+        let arr: Float32Array = num2array_ftz(data.into_iter(), 6);
+        let got: Vec<Option<u32>> = arr.iter().map(|o| o.map(f32::to_bits)).collect();
+        assert_eq!(
+            got,
+            vec![
+                Some(0),
+                Some(0),
+                Some(f32::MIN_POSITIVE.to_bits()),
+                Some((-0.0f32).to_bits()),
+                Some(1.0f32.to_bits()),
+                None,
+            ]
+        );
+
+        let h: half::f16 = half::f16::from_bits(1);
+        let arr: Float16Array = num2array_ftz(vec![h, half::f16::MIN_POSITIVE].into_iter(), 2);
+        assert_eq!(
+            array2vec(&arr),
+            vec![Some(half::f16::ZERO), Some(half::f16::MIN_POSITIVE)]
+        );
+    }
+
+    #[test]
+    fn test_num2array_with_bits() {
+        let data = vec![1.0, f64::NAN, -0.0, f64::from_bits(0x7ff8_0000_0000_0001)];
+        let (arr, bits) = num2array_with_bits(data.into_iter(), 4);
+        assert_eq!(arr.nulls(), bits.nulls());
+        let got: Vec<Option<u64>> = bits.iter().collect();
+        assert_eq!(got, vec![Some(1.0f64.to_bits()), None, Some(1 << 63), None]);
+        assert_eq!(bits.values()[1], 0);
+    }
 }